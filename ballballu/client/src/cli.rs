@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use clap::Parser;
+use shared::codec::WireCodec;
+
+/// Command line arguments
+/// Every flag can also be set through the BALLBALLU_* environment variable
+/// shown in `--help`.
+#[derive(Debug, Parser)]
+#[command(name = "client", about = "Ball Ball U game client")]
+pub struct ClientArgs {
+    /// Game server address: host:port or a full ws:// URL
+    #[arg(short, long, env = "BALLBALLU_SERVER", default_value = "0.0.0.0:34568")]
+    pub server: String,
+
+    /// Player name; skips the name entry screen
+    #[arg(short, long, env = "BALLBALLU_NAME")]
+    pub name: Option<String>,
+
+    /// Room to play in (created on the server if it does not exist);
+    /// without it the server picks a room with a free slot
+    #[arg(short, long, env = "BALLBALLU_ROOM")]
+    pub room: Option<String>,
+
+    /// Window width in pixels
+    #[arg(long, env = "BALLBALLU_WIDTH", default_value_t = 1280)]
+    pub width: i32,
+
+    /// Window height in pixels
+    #[arg(long, env = "BALLBALLU_HEIGHT", default_value_t = 720)]
+    pub height: i32,
+
+    /// Start in fullscreen
+    #[arg(long, env = "BALLBALLU_FULLSCREEN")]
+    pub fullscreen: bool,
+
+    /// Wire codec: `binary` (bincode, small) or `json` (readable in packet captures)
+    #[arg(long, env = "BALLBALLU_CODEC", default_value = "binary", value_parser = parse_codec)]
+    pub codec: WireCodec,
+
+    /// How far in the past remote players are drawn (milliseconds)
+    #[arg(long, env = "BALLBALLU_INTERP_DELAY_MS", default_value_t = 100)]
+    pub interp_delay_ms: u64,
+
+    /// Log filter, e.g. `info` or `client=debug` (RUST_LOG takes precedence)
+    #[arg(long, env = "BALLBALLU_LOG", default_value = "info")]
+    pub log: String,
+
+    /// Also append JSON log lines to this file
+    #[arg(long, env = "BALLBALLU_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+}
+
+fn parse_codec(name: &str) -> Result<WireCodec, String> {
+    WireCodec::parse(name).ok_or_else(|| format!("unknown codec `{}` (binary or json)", name))
+}
+
+/// Arguments of this process (parsed once; window_conf runs before main)
+pub fn args() -> &'static ClientArgs {
+    static ARGS: OnceLock<ClientArgs> = OnceLock::new();
+    ARGS.get_or_init(ClientArgs::parse)
+}
+
+/// Turn a server address into the WebSocket URL to connect to
+/// `host:port` becomes `ws://host:port/?codec=<codec>`; URLs that already
+/// carry a query string are used as given.
+pub fn websocket_url(addr: &str, codec: WireCodec) -> String {
+    let addr = addr.trim();
+    let url = if addr.contains("://") { addr.to_string() } else { format!("ws://{}", addr) };
+    if url.contains('?') {
+        return url;
+    }
+    let has_path = url.split_once("://").is_some_and(|(_, rest)| rest.contains('/'));
+    let slash = if has_path { "" } else { "/" };
+    format!("{}{}?codec={}", url, slash, codec.as_str())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_defaults_to_binary_and_can_be_switched() {
+        let args = ClientArgs::try_parse_from(["client"]).unwrap();
+        assert_eq!(args.codec, WireCodec::Binary);
+        let args = ClientArgs::try_parse_from(["client", "--codec", "json"]).unwrap();
+        assert_eq!(args.codec, WireCodec::Json);
+        assert!(ClientArgs::try_parse_from(["client", "--codec", "xml"]).is_err());
+    }
+
+    #[test]
+    fn websocket_url_carries_the_codec() {
+        assert_eq!(websocket_url("localhost:34568", WireCodec::Json), "ws://localhost:34568/?codec=json");
+        assert_eq!(websocket_url(" ws://host:1/game ", WireCodec::Binary), "ws://host:1/game?codec=binary");
+        assert_eq!(websocket_url("ws://host:1/?codec=json", WireCodec::Binary), "ws://host:1/?codec=json");
+    }
+}
@@ -1,24 +1,40 @@
 use macroquad::prelude::*;
 use macroquad::prelude::{is_key_pressed,is_key_down, KeyCode};
 use shared::protocol::ClientMessage;
+use shared::protocol::UserInput;
+use crate::prediction::MoveInput;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+// Steering inputs and held arrow keys are sent at most this often (one per server tick is enough)
+const SEND_INTERVAL: Duration = Duration::from_millis(50);
+
+const ARROW_KEYS: [KeyCode; 4] = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right];
+
 pub struct InputManager {
     input_tx: mpsc::UnboundedSender<ClientMessage>,
+    sequence_number: u64,            // last Input / Move sequence number sent
+    last_steer: Option<(Instant, (f32, f32))>, // when / what was last streamed
+    last_step: Option<Instant>,      // when the last Move was sent
+    sent_input: Option<(u64, MoveInput)>, // sent this frame, for client prediction
 }
 
 impl InputManager {
     pub fn new(input_tx: mpsc::UnboundedSender<ClientMessage>) -> Self {
-        Self { input_tx }
+        Self { input_tx, sequence_number: 0, last_steer: None, last_step: None, sent_input: None }
     }
 
     /// Poll for keyboard input and send a single-step Move to the server.
+    /// A held arrow key repeats the Move once per server tick; the server
+    /// starts it when the running step ends.
     /// `player_radius` is used to scale the step distance; if `None`, a default
-    /// base distance is used. Returns (should_exit, enter_pressed).
-    pub fn poll_input(&self, player_radius: Option<f32>) -> (bool, bool) {
-        // Enter to send Ready message
+    /// base distance is used.
+    /// `steering` is the mouse offset from the player in world units when the
+    /// server runs in continuous mode; it replaces arrow key steps.
+    /// Returns (should_exit, enter_pressed).
+    pub fn poll_input(&mut self, player_radius: Option<f32>, steering: Option<(f32, f32)>) -> (bool, bool) {
+        // Enter is left to the caller (ready toggle in the lobby)
         if is_key_pressed(KeyCode::Enter) {
-            let _ = self.input_tx.send(ClientMessage::Ready);
             return (false, true);
         }
 
@@ -26,31 +42,44 @@ impl InputManager {
         let mut dy = 0.0f32;
         let mut has_press = false;
 
-        // Use key down for discrete movement
-        if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
+        // Use key down for discrete movement (arrow keys; W ejects mass), a new press is sent at once
+        let newly_pressed = ARROW_KEYS.iter().any(|key| is_key_pressed(*key));
+        if is_key_down(KeyCode::Up) {
             dy -= 1.0;
             has_press = true;
         }
-        if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
+        if is_key_down(KeyCode::Down) {
             dy += 1.0;
             has_press = true;
         }
-        if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
+        if is_key_down(KeyCode::Left) {
             dx -= 1.0;
             has_press = true;
         }
-        if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
+        if is_key_down(KeyCode::Right) {
             dx += 1.0;
             has_press = true;
         }
 
+        // Space to split (new halves fly in the last move direction)
+        if is_key_pressed(KeyCode::Space) {
+            let _ = self.input_tx.send(ClientMessage::Split);
+        }
+
+        // W to eject a pellet of mass
+        if is_key_pressed(KeyCode::W) {
+            let _ = self.input_tx.send(ClientMessage::EjectMass);
+        }
+
         // ESC to quit
         if is_key_pressed(KeyCode::Escape) {
             let _ = self.input_tx.send(ClientMessage::Quit);
             return (true, false);
         }
 
-        if has_press {
+        if let Some(target) = steering {
+            self.stream_steering(target);
+        } else if has_press && (newly_pressed || self.last_step.is_none_or(|at| at.elapsed() >= SEND_INTERVAL)) {
             // Normalize diagonal movement
             let mag = (dx * dx + dy * dy).sqrt();
             let ndx = if mag > 0.0 { dx / mag } else { 0.0 };
@@ -62,10 +91,33 @@ impl InputManager {
             // Scale factor: one click moves approximately two radii.
             let distance = base_radius * 2.0;
 
-            let msg = ClientMessage::Move { dx: ndx, dy: ndy, distance };
+            self.sequence_number += 1;
+            let msg = ClientMessage::Move { dx: ndx, dy: ndy, distance, sequence_number: self.sequence_number };
             let _ = self.input_tx.send(msg);
+            self.last_step = Some(Instant::now());
+            self.sent_input = Some((self.sequence_number, MoveInput::Step { dx: ndx, dy: ndy, distance }));
         }
 
         (false, false)
     }
+
+    /// Send the mouse target as a sequenced Input if it changed (rate limited)
+    fn stream_steering(&mut self, (dx, dy): (f32, f32)) {
+        if let Some((at, last)) = self.last_steer {
+            let moved = (last.0 - dx).abs() > 0.5 || (last.1 - dy).abs() > 0.5;
+            if at.elapsed() < SEND_INTERVAL || !moved {
+                return;
+            }
+        }
+        self.sequence_number += 1;
+        let input = UserInput { dx, dy, sequence_number: self.sequence_number };
+        let _ = self.input_tx.send(ClientMessage::Input { input });
+        self.last_steer = Some((Instant::now(), (dx, dy)));
+        self.sent_input = Some((self.sequence_number, MoveInput::Steer { dx, dy }));
+    }
+
+    /// Movement input sent during the last poll, if any
+    pub fn take_sent_input(&mut self) -> Option<(u64, MoveInput)> {
+        self.sent_input.take()
+    }
 }
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use shared::GameSnapshot;
+use shared::objects::PlayerSpec;
+use crate::websocket::ClientSnapshot;
+
+// Don't guess further ahead than this when snapshots stop arriving
+const MAX_EXTRAPOLATION_SECS: f32 = 0.25;
+
+/// Snapshot interpolation buffer
+/// Keeps the last few snapshots (by tick) and renders the world `delay`
+/// in the past, blending the two snapshots around that moment so remote
+/// players move smoothly even when packets arrive unevenly.
+///    - render time older than the buffer: oldest snapshot as is
+///    - render time newer than the buffer (packets late): extrapolate the
+///      newest snapshot with vx / vy, like before
+///    - the local player is always taken from the newest snapshot (it is
+///      either predicted or should react without the extra delay)
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<ClientSnapshot>,
+    capacity: usize,
+    delay: Duration,
+    anchor: Instant, // local time origin for the tick clock below
+}
+
+impl SnapshotBuffer {
+    pub fn new(capacity: usize, delay: Duration) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+            delay,
+            anchor: Instant::now(),
+        }
+    }
+
+    /// Add a snapshot; out of order or duplicate ticks are dropped
+    pub fn push(&mut self, snap: ClientSnapshot) {
+        if let Some(newest) = self.snapshots.back() {
+            if snap.snapshot.tick <= newest.snapshot.tick {
+                // Server restarted its tick counter: start over
+                if snap.snapshot.tick + self.capacity as u64 >= newest.snapshot.tick {
+                    return;
+                }
+                self.snapshots.clear();
+            }
+        }
+        self.snapshots.push_back(snap);
+        while self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// World state to draw now
+    /// Positions in the result are already valid for the current instant,
+    /// so `received_at` is set to now (no further extrapolation needed).
+    pub fn sample(&self, local_id: Option<u64>) -> Option<ClientSnapshot> {
+        let newest = self.snapshots.back()?;
+        let now = Instant::now();
+        let tick_ms = newest.snapshot.constants.tick_interval_ms.max(1) as f64;
+
+        // Tick clock: offset between local time and tick * tick_ms, taken from
+        // the snapshot that arrived with the least delay
+        let tick_time = |s: &ClientSnapshot| s.snapshot.tick as f64 * tick_ms;
+        let local_ms = |t: Instant| t.saturating_duration_since(self.anchor).as_secs_f64() * 1000.0;
+        let offset = self
+            .snapshots
+            .iter()
+            .map(|s| local_ms(s.received_at) - tick_time(s))
+            .fold(f64::INFINITY, f64::min);
+        let render_time = local_ms(now) - offset - self.delay.as_secs_f64() * 1000.0;
+
+        let mut out = newest.snapshot.clone();
+        let after = self.snapshots.iter().position(|s| tick_time(s) >= render_time);
+        match after {
+            // Older than anything buffered
+            Some(0) => {
+                let oldest = &self.snapshots[0].snapshot;
+                copy_entities(&mut out, oldest, oldest, 0.0, local_id);
+            }
+            // Between two snapshots
+            Some(i) => {
+                let (a, b) = (&self.snapshots[i - 1], &self.snapshots[i]);
+                let span = tick_time(b) - tick_time(a);
+                let t = if span > 0.0 { ((render_time - tick_time(a)) / span) as f32 } else { 1.0 };
+                copy_entities(&mut out, &a.snapshot, &b.snapshot, t, local_id);
+            }
+            // Buffer ran dry: extrapolate from the newest snapshot
+            None => {
+                let ahead = (((render_time - tick_time(newest)) / 1000.0) as f32).min(MAX_EXTRAPOLATION_SECS);
+                extrapolate(&mut out, ahead, local_id);
+            }
+        }
+
+        // The local player is not delayed
+        if let Some(id) = local_id {
+            let late = newest.received_at.elapsed().as_secs_f32();
+            if let Some(me) = out.players.iter_mut().find(|p| p.id == id) {
+                extrapolate_player(me, late);
+            }
+        }
+
+        Some(ClientSnapshot { snapshot: out, received_at: now, epoch: newest.epoch })
+    }
+}
+
+/// Put remote entities of `out` (a copy of the newest snapshot) at the blend
+/// of `a` and `b` at `t` (0 = a, 1 = b). Entities missing from either side
+/// keep the newest position.
+fn copy_entities(out: &mut GameSnapshot, a: &GameSnapshot, b: &GameSnapshot, t: f32, local_id: Option<u64>) {
+    let players_a: HashMap<u64, &PlayerSpec> = a.players.iter().map(|p| (p.id, p)).collect();
+    let players_b: HashMap<u64, &PlayerSpec> = b.players.iter().map(|p| (p.id, p)).collect();
+    for player in out.players.iter_mut().filter(|p| Some(p.id) != local_id) {
+        let (Some(pa), Some(pb)) = (players_a.get(&player.id), players_b.get(&player.id)) else {
+            continue;
+        };
+        player.x = lerp(pa.x, pb.x, t);
+        player.y = lerp(pa.y, pb.y, t);
+        player.radius = lerp(pa.radius, pb.radius, t);
+        // Cells that only exist in `b` (fresh splits) are drawn where `b` has them
+        player.cells = pb.cells.clone();
+        for cell in player.cells.iter_mut() {
+            if let Some(ca) = pa.cells.iter().find(|c| c.id == cell.id) {
+                cell.x = lerp(ca.x, cell.x, t);
+                cell.y = lerp(ca.y, cell.y, t);
+                cell.radius = lerp(ca.radius, cell.radius, t);
+            }
+        }
+        // Positions are final, no velocity-based extrapolation on top
+        player.vx = 0.0;
+        player.vy = 0.0;
+    }
+
+    let dots_a: HashMap<u64, (f32, f32)> = a.dots.iter().map(|d| (d.id, (d.x, d.y))).collect();
+    let dots_b: HashMap<u64, (f32, f32)> = b.dots.iter().map(|d| (d.id, (d.x, d.y))).collect();
+    for dot in out.dots.iter_mut() {
+        if let (Some(da), Some(db)) = (dots_a.get(&dot.id), dots_b.get(&dot.id)) {
+            dot.x = lerp(da.0, db.0, t);
+            dot.y = lerp(da.1, db.1, t);
+        }
+    }
+
+    for virus in out.viruses.iter_mut() {
+        let va = a.viruses.iter().find(|v| v.id == virus.id);
+        let vb = b.viruses.iter().find(|v| v.id == virus.id);
+        if let (Some(va), Some(vb)) = (va, vb) {
+            virus.x = lerp(va.x, vb.x, t);
+            virus.y = lerp(va.y, vb.y, t);
+            virus.vx = 0.0;
+            virus.vy = 0.0;
+        }
+    }
+}
+
+/// Move remote entities `seconds` ahead along their velocity
+fn extrapolate(out: &mut GameSnapshot, seconds: f32, local_id: Option<u64>) {
+    for player in out.players.iter_mut().filter(|p| Some(p.id) != local_id) {
+        extrapolate_player(player, seconds);
+    }
+    for virus in out.viruses.iter_mut() {
+        virus.x += virus.vx * seconds;
+        virus.y += virus.vy * seconds;
+        virus.vx = 0.0;
+        virus.vy = 0.0;
+    }
+}
+
+fn extrapolate_player(player: &mut PlayerSpec, seconds: f32) {
+    let (dx, dy) = (player.vx * seconds, player.vy * seconds);
+    player.x += dx;
+    player.y += dy;
+    for cell in player.cells.iter_mut() {
+        cell.x += dx;
+        cell.y += dy;
+    }
+    player.vx = 0.0;
+    player.vy = 0.0;
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{GameConstant, GameStatus, LobbyState, MovementMode, RoundState};
+    use shared::objects::Cell;
+
+    const LOCAL: u64 = 2;
+    const REMOTE: u64 = 1;
+
+    fn player(id: u64, x: f32, vx: f32) -> PlayerSpec {
+        PlayerSpec {
+            id,
+            name: format!("p{}", id),
+            x,
+            y: 0.0,
+            radius: 20.0,
+            score: 10,
+            speed: 100.0,
+            sequence_number: 0,
+            remaining_distance: 0.0,
+            vx,
+            vy: 0.0,
+            cells: vec![Cell { id: id * 10, x, y: 0.0, radius: 20.0, score: 10, boost_vx: 0.0, boost_vy: 0.0, merge_tick: 0 }],
+            team: None,
+            bot: false,
+        }
+    }
+
+    /// Snapshot at `tick` with the remote player at `x` and the local one at `-x`
+    fn snapshot(tick: u64, x: f32) -> GameSnapshot {
+        GameSnapshot {
+            tick,
+            status: GameStatus::Playing,
+            players: vec![player(REMOTE, x, 400.0), player(LOCAL, -x, 0.0)],
+            dots: Vec::new(),
+            viruses: Vec::new(),
+            leaderboard: Vec::new(),
+            teams: Vec::new(),
+            lobby: LobbyState::default(),
+            round: RoundState::default(),
+            constants: GameConstant {
+                tick_interval_ms: 50,
+                collide_size_fraction: 1.1,
+                move_speed_base: 100.0,
+                dot_radius: 5.0,
+                world_width: 1000.0,
+                world_height: 1000.0,
+                movement_mode: MovementMode::Continuous,
+                team_count: 0,
+            },
+        }
+    }
+
+    /// Buffer holding ticks 100 (x = 100) and 102 (x = 200), the newest one
+    /// received `age` ago and both on the same tick clock
+    fn buffer(delay_ms: u64, age: Duration) -> SnapshotBuffer {
+        let now = Instant::now();
+        let mut buffer = SnapshotBuffer::new(8, Duration::from_millis(delay_ms));
+        buffer.anchor = now - Duration::from_secs(10) - age;
+        let newest_at = now - age;
+        buffer.push(ClientSnapshot { snapshot: snapshot(100, 100.0), received_at: newest_at - Duration::from_millis(100), epoch: 0 });
+        buffer.push(ClientSnapshot { snapshot: snapshot(102, 200.0), received_at: newest_at, epoch: 0 });
+        buffer
+    }
+
+    fn x_of(snap: &ClientSnapshot, id: u64) -> f32 {
+        snap.snapshot.players.iter().find(|p| p.id == id).unwrap().x
+    }
+
+    #[test]
+    fn empty_buffer_has_nothing_to_draw() {
+        assert!(SnapshotBuffer::new(8, Duration::ZERO).sample(None).is_none());
+    }
+
+    #[test]
+    fn blends_remote_players_between_two_snapshots() {
+        let out = buffer(50, Duration::ZERO).sample(Some(LOCAL)).unwrap();
+        assert_eq!(out.snapshot.tick, 102);
+        let remote = out.snapshot.players.iter().find(|p| p.id == REMOTE).unwrap();
+        assert!((remote.x - 150.0).abs() < 1.0, "x = {}", remote.x);
+        assert!((remote.cells[0].x - 150.0).abs() < 1.0);
+        assert_eq!(remote.vx, 0.0);
+        // The local player is not delayed
+        assert_eq!(x_of(&out, LOCAL), -200.0);
+    }
+
+    #[test]
+    fn clamps_to_the_oldest_snapshot() {
+        let out = buffer(1000, Duration::ZERO).sample(Some(LOCAL)).unwrap();
+        assert_eq!(x_of(&out, REMOTE), 100.0);
+        assert_eq!(x_of(&out, LOCAL), -200.0);
+    }
+
+    #[test]
+    fn extrapolates_the_newest_snapshot_for_a_limited_time() {
+        let out = buffer(0, Duration::from_secs(2)).sample(Some(LOCAL)).unwrap();
+        let expected = 200.0 + 400.0 * MAX_EXTRAPOLATION_SECS;
+        assert!((x_of(&out, REMOTE) - expected).abs() < 0.01, "x = {}", x_of(&out, REMOTE));
+    }
+
+    #[test]
+    fn drops_duplicates_and_starts_over_after_a_tick_reset() {
+        let mut buffer = buffer(0, Duration::ZERO);
+        buffer.push(ClientSnapshot { snapshot: snapshot(101, 0.0), received_at: Instant::now(), epoch: 0 });
+        assert_eq!(buffer.snapshots.len(), 2);
+
+        let mut restarted = SnapshotBuffer::new(2, Duration::ZERO);
+        restarted.push(ClientSnapshot { snapshot: snapshot(100, 0.0), received_at: Instant::now(), epoch: 0 });
+        restarted.push(ClientSnapshot { snapshot: snapshot(3, 0.0), received_at: Instant::now(), epoch: 0 });
+        assert_eq!(restarted.snapshots.iter().map(|s| s.snapshot.tick).collect::<Vec<_>>(), vec![3]);
+    }
+}
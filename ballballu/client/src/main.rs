@@ -1,318 +1,834 @@
 mod render_manager;
 mod input_manager;
 mod websocket;
+mod prediction;
+mod interpolation;
+mod cli;
 
 use tokio::runtime::Runtime;
 use macroquad::prelude::*;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::mpsc;
-use shared::{GameSnapshot, protocol::{ServerMessage, ClientMessage}};
-use serde_json;
-use crate::websocket::ClientSnapshot;
-use std::time::Instant;
+use shared::{GameConstant, GameSnapshot, GameStatus, MovementMode, protocol::{ServerMessage, ClientMessage, WelcomeMessage, RoomInfo, RoomListMessage, HallOfFameMessage, AnnouncementMessage}};
+use shared::codec::{self, Frame, WireCodec};
+use shared::logging;
+use crate::websocket::{ClientSnapshot, SnapshotAssembler};
+use crate::prediction::Predictor;
+use crate::interpolation::SnapshotBuffer;
+use crate::render_manager::{RenderManager, StartPage};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+// ~1.5s of snapshots at 20 ticks/sec
+const SNAPSHOT_BUFFER_LEN: usize = 32;
+const MAX_NAME_LEN: usize = 15;
+const MAX_SERVER_ADDR_LEN: usize = 64;
+// The start page refreshes the room list this often
+const ROOM_LIST_INTERVAL: Duration = Duration::from_secs(2);
+// Hall of fame refresh on the start page (a database query on the server)
+const HALL_OF_FAME_INTERVAL: Duration = Duration::from_secs(15);
+// Reconnect backoff: 0.5s, 1s, 2s, ... up to 10s between attempts
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
 
 fn window_conf() -> Conf {
+    let args = cli::args();
     Conf {
         window_title: "Ball Ball U".to_owned(),
-        window_width: 1280,
-        window_height: 720,
+        window_width: args.width,
+        window_height: args.height,
+        fullscreen: args.fullscreen,
         window_resizable: true,
         ..Default::default()
     }
 }
 
+/// State owned by the websocket read task
+/// Channels to the main loop + snapshot reconstruction from deltas
+struct ReadContext {
+    snapshot_tx: mpsc::UnboundedSender<ClientSnapshot>,
+    welcome_tx: mpsc::UnboundedSender<WelcomeMessage>,
+    room_tx: mpsc::UnboundedSender<RoomListMessage>,
+    hall_tx: mpsc::UnboundedSender<HallOfFameMessage>,
+    announcement_tx: mpsc::UnboundedSender<AnnouncementMessage>,
+    ack_tx: mpsc::UnboundedSender<ClientMessage>,  // acks go out through the input writer
+    assembler: SnapshotAssembler,
+    epoch: u64,           // from the newest Welcome, stamped on acks and snapshots
+    bye: Option<String>,  // reason the server gave for closing the connection
+}
+
+impl ReadContext {
+    /// Forward a full snapshot to the main loop and acknowledge it
+    /// Returns true if the main loop is gone
+    fn deliver(&self, snapshot: GameSnapshot) -> bool {
+        let _ = self.ack_tx.send(ClientMessage::Ack { tick: snapshot.tick, epoch: self.epoch });
+        self.snapshot_tx
+            .send(ClientSnapshot {
+                snapshot,
+                received_at: Instant::now(),
+                epoch: self.epoch,
+            })
+            .is_err()
+    }
+}
+
+/// Live connection to a game server
+/// Channels to / from the websocket read and write tasks
+struct Connection {
+    url: String,
+    input_tx: mpsc::UnboundedSender<ClientMessage>,
+    snapshot_rx: mpsc::UnboundedReceiver<ClientSnapshot>,
+    welcome_rx: mpsc::UnboundedReceiver<WelcomeMessage>,
+    room_rx: mpsc::UnboundedReceiver<RoomListMessage>,
+    hall_rx: mpsc::UnboundedReceiver<HallOfFameMessage>,
+    announcement_rx: mpsc::UnboundedReceiver<AnnouncementMessage>,
+    shutdown_rx: mpsc::UnboundedReceiver<Option<String>>,  // the Bye reason, if any
+    read_handle: tokio::task::JoinHandle<()>,
+    write_handle: tokio::task::JoinHandle<()>,
+}
+
+impl Connection {
+    /// Connect to `url` and start the read / write tasks
+    fn open(rt: &Runtime, url: &str, wire_codec: WireCodec) -> Result<Self, String> {
+        info!("Connecting to {}", url);
+        let ws_stream = rt.block_on(connect(url.to_string()))?;
+        Ok(Self::start(rt, url, ws_stream, wire_codec))
+    }
+
+    /// Start the read / write tasks on an open websocket
+    fn start(rt: &Runtime, url: &str, ws_stream: WsStream, wire_codec: WireCodec) -> Self {
+        info!("Connected to {}", url);
+        // Everything logged by the read / write tasks carries the server URL
+        let span = info_span!("conn", %url);
+
+        let (mut write, mut read) = ws_stream.split();
+
+        // Channel to send game snapshots from websocket task to main loop
+        let (snapshot_tx, snapshot_rx) = mpsc::unbounded_channel::<ClientSnapshot>();
+
+        // Channel to send input commands to server
+        let (input_tx, mut input_rx) = mpsc::unbounded_channel::<ClientMessage>();
+
+        // Channel to signal shutdown (with the reason if the server said goodbye)
+        let (shutdown_tx, shutdown_rx) = mpsc::unbounded_channel::<Option<String>>();
+
+        // Channel to send the Welcome (player_id + constants) from websocket task to main loop
+        let (welcome_tx, welcome_rx) = mpsc::unbounded_channel::<WelcomeMessage>();
+
+        // Channel to send room lists from websocket task to main loop
+        let (room_tx, room_rx) = mpsc::unbounded_channel::<RoomListMessage>();
+
+        // Channel to send the hall of fame from websocket task to main loop
+        let (hall_tx, hall_rx) = mpsc::unbounded_channel::<HallOfFameMessage>();
+
+        // Channel to send server announcements from websocket task to main loop
+        let (announcement_tx, announcement_rx) = mpsc::unbounded_channel::<AnnouncementMessage>();
+
+        // Spawn a task to receive messages from the server
+        let mut read_ctx = ReadContext {
+            snapshot_tx,
+            welcome_tx,
+            room_tx,
+            hall_tx,
+            announcement_tx,
+            ack_tx: input_tx.clone(),
+            assembler: SnapshotAssembler::new(64),
+            epoch: 0,
+            bye: None,
+        };
+        let read_handle = rt.spawn(async move {
+            while let Some(msg) = read.next().await {
+                let should_break = match msg {
+                    Ok(Message::Text(text)) => {
+                        handle_text_message(&text, &mut read_ctx)
+                    }
+                    Ok(Message::Binary(bytes)) => {
+                        handle_binary_message(&bytes, &mut read_ctx)
+                    }
+                    Ok(Message::Close(_)) => {
+                        info!("Server closed connection");
+                        true
+                    }
+                    Err(e) => {
+                        warn!("WebSocket error: {:?}", e);
+                        true
+                    }
+                    _ => false, // Ignore other message types
+                };
+
+                if should_break {
+                    break;
+                }
+            }
+            // Notify main loop that server disconnected
+            let _ = shutdown_tx.send(read_ctx.bye.take());
+        }.instrument(span.clone()));
+
+        // Spawn a task to send input commands to server
+        let write_handle = rt.spawn(async move {
+            while let Some(msg) = input_rx.recv().await {
+                let frame = match codec::encode(&msg, wire_codec) {
+                    Frame::Text(text) => Message::Text(text),
+                    Frame::Binary(bytes) => Message::Binary(bytes),
+                };
+                if write.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        }.instrument(span));
+
+        Self {
+            url: url.to_string(),
+            input_tx,
+            snapshot_rx,
+            welcome_rx,
+            room_rx,
+            hall_rx,
+            announcement_rx,
+            shutdown_rx,
+            read_handle,
+            write_handle,
+        }
+    }
+
+    /// Switch to the requested room (if any) and ask for the room list and
+    /// the hall of fame
+    fn enter_room(&self, room: Option<&str>) {
+        if let Some(room) = room {
+            let _ = self.input_tx.send(ClientMessage::JoinRoom { room: room.to_string() });
+        }
+        self.list_rooms();
+        self.hall_of_fame();
+    }
+
+    fn hall_of_fame(&self) {
+        let _ = self.input_tx.send(ClientMessage::HallOfFame);
+    }
+
+    fn list_rooms(&self) {
+        let _ = self.input_tx.send(ClientMessage::ListRooms);
+    }
+
+    /// Join the game with this name and mark ready
+    fn join(&self, name: &str) {
+        let _ = self.input_tx.send(ClientMessage::Join { name: name.to_string() });
+        let _ = self.input_tx.send(ClientMessage::Ready);
+    }
+
+    /// Ready / take back Ready in the lobby
+    fn set_ready(&self, ready: bool) {
+        let message = if ready { ClientMessage::Ready } else { ClientMessage::Unready };
+        let _ = self.input_tx.send(message);
+    }
+
+    /// Switch team (team mode, lobby only)
+    fn choose_team(&self, team: u8) {
+        let _ = self.input_tx.send(ClientMessage::ChooseTeam { team });
+    }
+
+    /// Take back the player from before a reconnect
+    fn resume(&self, token: &str) {
+        let _ = self.input_tx.send(ClientMessage::Resume { token: token.to_string() });
+    }
+
+    fn is_finished(&self) -> bool {
+        self.read_handle.is_finished() || self.write_handle.is_finished()
+    }
+
+    fn close(self) {
+        self.read_handle.abort();
+        self.write_handle.abort();
+    }
+}
+
+/// Open a websocket to `url`
+async fn connect(url: String) -> Result<WsStream, String> {
+    connect_async(&url)
+        .await
+        .map(|(ws_stream, _)| ws_stream)
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))
+}
+
+/// Automatic reconnection after the connection dropped mid-game
+/// Attempts run in the background (the window keeps drawing) and back off
+/// exponentially between failures.
+struct Reconnect {
+    url: String,
+    attempt: u32,
+    next_try: Instant,
+    pending: Option<tokio::task::JoinHandle<Result<WsStream, String>>>,
+}
+
+impl Reconnect {
+    fn new(url: String) -> Self {
+        Self { url, attempt: 0, next_try: Instant::now(), pending: None }
+    }
+
+    /// Drive the current attempt; Some once a new websocket is open
+    fn poll(&mut self, rt: &Runtime) -> Option<WsStream> {
+        match self.pending.take() {
+            None => {
+                if Instant::now() >= self.next_try {
+                    self.attempt += 1;
+                    info!(attempt = self.attempt, "Reconnecting to {}", self.url);
+                    self.pending = Some(rt.spawn(connect(self.url.clone())));
+                }
+                None
+            }
+            Some(handle) if !handle.is_finished() => {
+                self.pending = Some(handle);
+                None
+            }
+            Some(handle) => match rt.block_on(handle) {
+                Ok(Ok(ws_stream)) => Some(ws_stream),
+                Ok(Err(e)) => {
+                    warn!("{}", e);
+                    self.next_try = Instant::now() + self.delay();
+                    None
+                }
+                Err(e) => {
+                    error!("Reconnect task failed: {}", e);
+                    self.next_try = Instant::now() + self.delay();
+                    None
+                }
+            },
+        }
+    }
+
+    /// Wait before the next attempt, doubling with every failure
+    fn delay(&self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempt.saturating_sub(1).min(16));
+        RECONNECT_BASE_DELAY.saturating_mul(factor).min(RECONNECT_MAX_DELAY)
+    }
+
+    /// Status line for the connection lost overlay
+    fn status(&self) -> String {
+        let wait = self.next_try.saturating_duration_since(Instant::now());
+        if self.pending.is_some() || wait.is_zero() {
+            format!("Reconnecting... (attempt {})", self.attempt.max(1))
+        } else {
+            format!("Reconnecting in {:.1}s (attempt {})", wait.as_secs_f32(), self.attempt + 1)
+        }
+    }
+
+    fn cancel(self) {
+        if let Some(handle) = self.pending {
+            handle.abort();
+        }
+    }
+}
+
+/// Character typed into the name field for a key (names are upper case)
+fn name_char(key: KeyCode) -> Option<char> {
+    let c = match key {
+        KeyCode::A => 'A',
+        KeyCode::B => 'B',
+        KeyCode::C => 'C',
+        KeyCode::D => 'D',
+        KeyCode::E => 'E',
+        KeyCode::F => 'F',
+        KeyCode::G => 'G',
+        KeyCode::H => 'H',
+        KeyCode::I => 'I',
+        KeyCode::J => 'J',
+        KeyCode::K => 'K',
+        KeyCode::L => 'L',
+        KeyCode::M => 'M',
+        KeyCode::N => 'N',
+        KeyCode::O => 'O',
+        KeyCode::P => 'P',
+        KeyCode::Q => 'Q',
+        KeyCode::R => 'R',
+        KeyCode::S => 'S',
+        KeyCode::T => 'T',
+        KeyCode::U => 'U',
+        KeyCode::V => 'V',
+        KeyCode::W => 'W',
+        KeyCode::X => 'X',
+        KeyCode::Y => 'Y',
+        KeyCode::Z => 'Z',
+        KeyCode::Key0 => '0',
+        KeyCode::Key1 => '1',
+        KeyCode::Key2 => '2',
+        KeyCode::Key3 => '3',
+        KeyCode::Key4 => '4',
+        KeyCode::Key5 => '5',
+        KeyCode::Key6 => '6',
+        KeyCode::Key7 => '7',
+        KeyCode::Key8 => '8',
+        KeyCode::Key9 => '9',
+        KeyCode::Space => ' ',
+        KeyCode::Minus => '_',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Team picked with the number keys 1..=team_count this frame (team mode)
+fn team_key(team_count: usize) -> Option<u8> {
+    const KEYS: [KeyCode; shared::MAX_TEAMS] = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+    ];
+    KEYS.iter().take(team_count).position(|key| is_key_pressed(*key)).map(|i| i as u8)
+}
+
+/// Per-connection game state of the main loop, reset on reconnect
+struct Session {
+    latest_snapshot: Option<ClientSnapshot>,
+    player_id: Option<u64>,
+    room: Option<String>,         // From the server's WelcomeMessage
+    epoch: u64,                   // Snapshots of other epochs are dropped
+    movement_mode: MovementMode,  // From the server's WelcomeMessage (or a later snapshot)
+    predictor: Predictor,         // Local player prediction
+    snapshot_buffer: SnapshotBuffer,
+    frames_without_update: u32,
+}
+
+impl Session {
+    fn new(interp_delay: Duration) -> Self {
+        Self {
+            latest_snapshot: None,
+            player_id: None,
+            room: None,
+            epoch: 0,
+            movement_mode: MovementMode::Discrete,
+            predictor: Predictor::new(),
+            snapshot_buffer: SnapshotBuffer::new(SNAPSHOT_BUFFER_LEN, interp_delay),
+            frames_without_update: 0,
+        }
+    }
+}
+
+/// Use new game constants (from the Welcome, or changed mid-game by the server)
+fn apply_constants(session: &mut Session, render_manager: &mut RenderManager, constants: &GameConstant) {
+    session.movement_mode = constants.movement_mode;
+    session.predictor.set_constants(constants.clone());
+    render_manager.set_world_size(constants.world_width, constants.world_height);
+}
+
+/// Show a single "connecting" frame before the blocking connect
+async fn draw_connecting(url: &str) {
+    clear_background(BLACK);
+    draw_text(
+        &format!("Connecting to {}...", url),
+        screen_width() / 2.0 - 250.0,
+        screen_height() / 2.0,
+        30.0,
+        WHITE,
+    );
+    next_frame().await;
+}
+
 /// Handle incoming text messages from the server
 /// Returns true if the connection should be closed
-fn handle_text_message(
-    text: &str,
-    snapshot_tx: &mpsc::UnboundedSender<ClientSnapshot>,
-    player_id_tx: &mpsc::UnboundedSender<u64>,
-) -> bool {
+fn handle_text_message(text: &str, ctx: &mut ReadContext) -> bool {
     // Try to parse as ServerMessage
     if let Ok(server_msg) = serde_json::from_str::<ServerMessage>(text) {
-        return handle_server_message(server_msg, snapshot_tx, player_id_tx);
+        return handle_server_message(server_msg, ctx);
     }
     
     // Try direct GameSnapshot parse as fallback
     if let Ok(snapshot) = serde_json::from_str::<GameSnapshot>(text) {
-        let _ = snapshot_tx.send(ClientSnapshot {
+        let _ = ctx.snapshot_tx.send(ClientSnapshot {
             snapshot,
             received_at: Instant::now(),
+            epoch: ctx.epoch,
         });
     }
     
     false
 }
 
+/// Handle incoming binary (bincode) messages from the server
+/// Returns true if the connection should be closed
+fn handle_binary_message(bytes: &[u8], ctx: &mut ReadContext) -> bool {
+    match codec::decode_binary::<ServerMessage>(bytes) {
+        Ok(server_msg) => handle_server_message(server_msg, ctx),
+        Err(e) => {
+            warn!("Failed to decode binary message: {:?}", e);
+            false
+        }
+    }
+}
+
 /// Handle parsed server messages
 /// Returns true if the connection should be closed
-fn handle_server_message(
-    msg: ServerMessage,
-    snapshot_tx: &mpsc::UnboundedSender<ClientSnapshot>,
-    player_id_tx: &mpsc::UnboundedSender<u64>,
-) -> bool {
+fn handle_server_message(msg: ServerMessage, ctx: &mut ReadContext) -> bool {
     match msg {
         ServerMessage::Welcome(welcome) => {
-            println!("Welcomed! Player ID: {}", welcome.player_id);
-            let _ = player_id_tx.send(welcome.player_id);
+            info!(player = welcome.player_id, room = %welcome.room, "Welcomed");
+            // Deltas from now on are based on snapshots of the new epoch only
+            if welcome.epoch != ctx.epoch {
+                ctx.epoch = welcome.epoch;
+                ctx.assembler.clear();
+            }
+            let _ = ctx.welcome_tx.send(welcome);
             false
         }
         ServerMessage::StateUpdate(state_update) => {
-            let snapshot = state_update.snapshot;
-            snapshot_tx
-                .send(ClientSnapshot {
-                    snapshot,
-                    received_at: Instant::now(),
-                })
-                .is_err()
+            let snapshot = ctx.assembler.on_full(state_update.snapshot);
+            ctx.deliver(snapshot)
+        }
+        ServerMessage::DeltaUpdate(delta_update) => {
+            // Unknown base: skip, the server falls back to a keyframe soon
+            match ctx.assembler.on_delta(&delta_update.delta) {
+                Some(snapshot) => ctx.deliver(snapshot),
+                None => false,
+            }
+        }
+        ServerMessage::RoomList(list) => {
+            let _ = ctx.room_tx.send(list);
+            false
+        }
+        ServerMessage::HallOfFame(hall_of_fame) => {
+            let _ = ctx.hall_tx.send(hall_of_fame);
+            false
         }
         ServerMessage::Bye(bye) => {
-            println!("Server says goodbye: {}", bye.reason);
+            info!("Server says goodbye: {}", bye.reason);
+            ctx.bye = Some(bye.reason);
             true
         }
+        ServerMessage::Announcement(announcement) => {
+            info!("Server announcement: {}", announcement.text);
+            let _ = ctx.announcement_tx.send(announcement);
+            false
+        }
     }
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
+    let args = cli::args();
+
+    // Log output (the guard flushes the JSON log file on exit)
+    let _log_guard = match logging::init(&args.log, args.log_file.as_deref()) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // +++Create tokio runtime manually (macroquad does NOT supply a reactor)
     let rt = Runtime::new().expect("Failed to create Tokio runtime");
 
-    let url = "ws://0.0.0.0:34568";
-    
-    // Initialize render manager with default world size
-    // These should match server's world size
-    let world_width = 2000.0;
-    let world_height = 2000.0;
-    let mut render_manager = render_manager::RenderManager::new(world_width, world_height);
+    // Binary frames are much smaller; `--codec json` to read traffic in devtools/wireshark
+    let wire_codec = args.codec;
+    let interp_delay = Duration::from_millis(args.interp_delay_ms);
 
-    println!("Connecting to {}", url);
+    // World size is replaced by the one in the WelcomeMessage
+    let mut render_manager = RenderManager::new(2000.0, 2000.0);
 
-    // Show connecting screen
-    clear_background(BLACK);
-    draw_text(
-        "Connecting to server...",
-        screen_width() / 2.0 - 150.0,
-        screen_height() / 2.0,
-        30.0,
-        WHITE,
-    );
-    next_frame().await;
+    let mut server_addr = args.server.clone();
+    let mut player_name: String = args.name.as_deref().unwrap_or("").trim().chars().take(MAX_NAME_LEN).collect();
+    let mut editing_server = false;      // TAB switches the start page field
+    let mut connect_error: Option<String> = None;
 
     // Connect to WebSocket server
-    // let (ws_stream, _) = match connect_async(url).await {
-    let (ws_stream, _) = match rt.block_on(async { connect_async(url).await }) {
+    let url = cli::websocket_url(&server_addr, wire_codec);
+    draw_connecting(&url).await;
+    let mut conn = match Connection::open(&rt, &url, wire_codec) {
         Ok(c) => {
-            println!("Successfully connected to server");
-            c
-        },
+            c.enter_room(args.room.as_deref());
+            Some(c)
+        }
         Err(e) => {
-            eprintln!("Failed to connect: {:?}", e);
-            // Show error on screen
-            loop {
-                clear_background(BLACK);
-                draw_text(
-                    "Failed to connect to server!",
-                    screen_width() / 2.0 - 200.0,
-                    screen_height() / 2.0 - 20.0,
-                    30.0,
-                    RED,
-                );
-                draw_text(
-                    &format!("Error: {:?}", e),
-                    screen_width() / 2.0 - 200.0,
-                    screen_height() / 2.0 + 20.0,
-                    20.0,
-                    Color::from_rgba(255, 150, 150, 255),
-                );
-                draw_text(
-                    "Press ESC to exit",
-                    screen_width() / 2.0 - 100.0,
-                    screen_height() / 2.0 + 60.0,
-                    20.0,
-                    WHITE,
-                );
-                if is_key_pressed(KeyCode::Escape) {
-                    break;
-                }
-                next_frame().await;
-            }
-            return;
+            warn!("{}", e);
+            connect_error = Some(e);
+            editing_server = true;
+            None
         }
     };
+    let mut input_manager = conn.as_ref().map(|c| input_manager::InputManager::new(c.input_tx.clone()));
+    let mut session = Session::new(interp_delay);
 
-    let (mut write, mut read) = ws_stream.split();
-
-    // Channel to send game snapshots from websocket task to main loop
-    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel::<ClientSnapshot>();
-    
-    // Channel to send input commands to server
-    let (input_tx, mut input_rx) = mpsc::unbounded_channel::<ClientMessage>();
-    
-    // Channel to signal shutdown
-    let (shutdown_tx, mut shutdown_rx) = mpsc::unbounded_channel::<()>();
-    
-    // Channel to send player_id from websocket task to main loop
-    let (player_id_tx, mut player_id_rx) = mpsc::unbounded_channel::<u64>();
-    
-    // Initialize input manager
-    let input_manager = input_manager::InputManager::new(input_tx.clone());
-
-    // Spawn a task to receive messages from the server
-    let read_handle = rt.spawn(async move {
-        while let Some(msg) = read.next().await {
-            let should_break = match msg {
-                Ok(Message::Text(text)) => {
-                    handle_text_message(&text, &snapshot_tx, &player_id_tx)
-                }
-                Ok(Message::Close(_)) => {
-                    println!("Server closed connection");
-                    true
-                }
-                Err(e) => {
-                    eprintln!("WebSocket error: {:?}", e);
-                    true
-                }
-                _ => false, // Ignore other message types
-            };
-            
-            if should_break {
-                break;
-            }
-        }
-        // Notify main loop that server disconnected
-        let _ = shutdown_tx.send(());
-    });
-
-    // Spawn a task to send input commands to server
-    let write_handle = rt.spawn(async move {
-        while let Some(msg) = input_rx.recv().await {
-            let json = serde_json::to_string(&msg).unwrap();
-            if write.send(Message::Text(json)).await.is_err() {
-                break;
-            }
-        }
-    });
-    
     // Main game loop
     let mut should_exit = false;
-    let mut latest_snapshot: Option<ClientSnapshot> = None;
-    let mut connection_lost = false;
-    let mut frames_without_update = 0;
-    let mut player_id: Option<u64> = None;
     let mut client_ready = false;  // Track if this client has pressed enter
-    let mut player_name = String::new();  // Player name input
     let mut name_submitted = false;  // Track if name has been submitted
     let mut join_time: Option<Instant> = None;  // Track when this client joined the game
+    let mut resume_token: Option<String> = None;  // From the last WelcomeMessage
+    let mut reconnect: Option<Reconnect> = None;   // Set while the connection is down mid-game
+    let mut rooms: Vec<RoomInfo> = Vec::new();     // Shown on the start page
+    let mut last_room_poll = Instant::now();
+    let mut hall_of_fame: Option<HallOfFameMessage> = None; // Shown on the start page
+    let mut last_hall_of_fame_poll = Instant::now();
+    let mut announcement: Option<(String, Instant)> = None; // Banner text, shown until then
+
+    // A preset name skips the name entry screen
+    if let (Some(c), Some(_)) = (conn.as_ref(), args.name.as_ref()) {
+        c.join(&player_name);
+        name_submitted = true;
+        client_ready = true;
+        join_time = Some(Instant::now());
+    }
 
     loop {
         // Check for shutdown signal (non-blocking)
-        if let Ok(_) = shutdown_rx.try_recv() {
-            connection_lost = true;
+        let mut bye: Option<String> = None;
+        let connection_lost = conn.as_mut().is_some_and(|c| match c.shutdown_rx.try_recv() {
+            Ok(reason) => {
+                bye = reason;
+                true
+            }
+            Err(_) => c.is_finished(),
+        });
+
+        // Connection dropped: in game, keep the world on screen and reconnect
+        // in the background; on the start page just show the error. A server
+        // that said goodbye (kick, ban) is not reconnected to.
+        if connection_lost {
+            if let Some(old) = conn.take() {
+                input_manager = None;
+                if let Some(reason) = bye {
+                    connect_error = Some(reason);
+                    name_submitted = false;
+                    client_ready = false;
+                    join_time = None;
+                    resume_token = None;
+                    session = Session::new(interp_delay);
+                } else if name_submitted {
+                    reconnect = Some(Reconnect::new(old.url.clone()));
+                } else {
+                    connect_error = Some(format!("Lost connection to {}", old.url));
+                }
+                old.close();
+            }
+        }
+        if let Some(ws_stream) = reconnect.as_mut().and_then(|r| r.poll(&rt)) {
+            let url = reconnect.take().map(|r| r.url).unwrap_or_default();
+            let c = Connection::start(&rt, &url, ws_stream, wire_codec);
+            // Resume first so Join / Ready apply to the old player; with an
+            // expired token they set up the fresh one instead
+            if let Some(token) = resume_token.as_ref() {
+                c.resume(token);
+            }
+            c.enter_room(args.room.as_deref());
+            c.join(&player_name);
+            input_manager = Some(input_manager::InputManager::new(c.input_tx.clone()));
+            session = Session::new(interp_delay);
+            conn = Some(c);
+        }
+        if reconnect.is_some() && is_key_pressed(KeyCode::Escape) {
             should_exit = true;
         }
 
-        // Handle text input for player name on start screen
+        // Handle text input for player name / server address on start screen
         if !name_submitted {
+            if is_key_pressed(KeyCode::Tab) {
+                editing_server = !editing_server;
+            }
+
             // Check for backspace
             if is_key_pressed(KeyCode::Backspace) {
-                if !player_name.is_empty() {
+                if editing_server {
+                    server_addr.pop();
+                } else {
                     player_name.pop();
                 }
             }
             
-            // Check for enter
+            // Check for enter: (re)connect if the address changed, then join
             if is_key_pressed(KeyCode::Enter) {
-                name_submitted = true;
-                client_ready = true;
-                join_time = Some(Instant::now());  // Record join time when player presses ENTER
-                let _ = input_tx.send(ClientMessage::Join { name: player_name.clone() });
-                let _ = input_tx.send(ClientMessage::Ready);
-            }
-            
-            // Handle alphanumeric and space input
-            if let Some(key) = get_last_key_pressed() {
-                if player_name.len() < 15 {
-                    match key {
-                        KeyCode::A => player_name.push('A'),
-                        KeyCode::B => player_name.push('B'),
-                        KeyCode::C => player_name.push('C'),
-                        KeyCode::D => player_name.push('D'),
-                        KeyCode::E => player_name.push('E'),
-                        KeyCode::F => player_name.push('F'),
-                        KeyCode::G => player_name.push('G'),
-                        KeyCode::H => player_name.push('H'),
-                        KeyCode::I => player_name.push('I'),
-                        KeyCode::J => player_name.push('J'),
-                        KeyCode::K => player_name.push('K'),
-                        KeyCode::L => player_name.push('L'),
-                        KeyCode::M => player_name.push('M'),
-                        KeyCode::N => player_name.push('N'),
-                        KeyCode::O => player_name.push('O'),
-                        KeyCode::P => player_name.push('P'),
-                        KeyCode::Q => player_name.push('Q'),
-                        KeyCode::R => player_name.push('R'),
-                        KeyCode::S => player_name.push('S'),
-                        KeyCode::T => player_name.push('T'),
-                        KeyCode::U => player_name.push('U'),
-                        KeyCode::V => player_name.push('V'),
-                        KeyCode::W => player_name.push('W'),
-                        KeyCode::X => player_name.push('X'),
-                        KeyCode::Y => player_name.push('Y'),
-                        KeyCode::Z => player_name.push('Z'),
-                        KeyCode::Key0 => player_name.push('0'),
-                        KeyCode::Key1 => player_name.push('1'),
-                        KeyCode::Key2 => player_name.push('2'),
-                        KeyCode::Key3 => player_name.push('3'),
-                        KeyCode::Key4 => player_name.push('4'),
-                        KeyCode::Key5 => player_name.push('5'),
-                        KeyCode::Key6 => player_name.push('6'),
-                        KeyCode::Key7 => player_name.push('7'),
-                        KeyCode::Key8 => player_name.push('8'),
-                        KeyCode::Key9 => player_name.push('9'),
-                        KeyCode::Space => player_name.push(' '),
-                        KeyCode::Minus => player_name.push('_'),
-                        _ => {}
+                let url = cli::websocket_url(&server_addr, wire_codec);
+                if conn.as_ref().is_none_or(|c| c.url != url) {
+                    if let Some(old) = conn.take() {
+                        old.close();
+                    }
+                    draw_connecting(&url).await;
+                    session = Session::new(interp_delay);
+                    match Connection::open(&rt, &url, wire_codec) {
+                        Ok(c) => {
+                            c.enter_room(args.room.as_deref());
+                            input_manager = Some(input_manager::InputManager::new(c.input_tx.clone()));
+                            conn = Some(c);
+                            connect_error = None;
+                        }
+                        Err(e) => {
+                            warn!("{}", e);
+                            connect_error = Some(e);
+                            input_manager = None;
+                        }
                     }
                 }
+                if let Some(c) = conn.as_ref() {
+                    c.join(&player_name);
+                    name_submitted = true;
+                    client_ready = true;
+                    join_time = Some(Instant::now());  // Record join time when player presses ENTER
+                }
+            } else if editing_server {
+                // Addresses need lower case, '.', ':' and '/'
+                while let Some(c) = get_char_pressed() {
+                    if c.is_ascii_graphic() && server_addr.len() < MAX_SERVER_ADDR_LEN {
+                        server_addr.push(c);
+                    }
+                }
+            } else if let Some(c) = get_last_key_pressed().and_then(name_char) {
+                // Handle alphanumeric and space input
+                if player_name.len() < MAX_NAME_LEN {
+                    player_name.push(c);
+                }
             }
-        } else {
+        } else if let Some(input_manager) = input_manager.as_mut() {
             // Poll for keyboard input (one-click movement). We pass the local
             // player's radius so the client can compute step distance.
-            let player_radius = latest_snapshot
+            let player_radius = session.latest_snapshot.as_ref().and_then(|s| {
+                let id = session.player_id?;
+                s.snapshot.players.iter().find(|p| p.id == id).map(|p| p.radius)
+            });
+            // Continuous mode: steer towards the mouse cursor instead
+            let steering = match session.movement_mode {
+                MovementMode::Continuous => {
+                    let (mouse_x, mouse_y) = mouse_position();
+                    Some(render_manager.screen_to_world_offset(mouse_x, mouse_y))
+                }
+                MovementMode::Discrete => None,
+            };
+            let (should_exit_input, enter_pressed) = input_manager.poll_input(player_radius, steering);
+            // Team mode: number keys pick a team while no round is running
+            let team_count = session.latest_snapshot.as_ref().map_or(0, |s| s.snapshot.constants.team_count);
+            let round_running = session
+                .latest_snapshot
                 .as_ref()
-                .and_then(|s| s.snapshot.players.first().map(|p| p.radius));
-            let (should_exit_input, _enter_pressed) = input_manager.poll_input(player_radius);
+                .is_some_and(|s| s.snapshot.status == GameStatus::Playing);
+            if let Some(team) = team_key(team_count).filter(|_| !round_running) {
+                if let Some(c) = conn.as_ref() {
+                    c.choose_team(team);
+                }
+            }
+            // Enter toggles Ready while the room is in its lobby
+            if enter_pressed {
+                let in_lobby = session
+                    .latest_snapshot
+                    .as_ref()
+                    .is_some_and(|s| s.snapshot.status == GameStatus::WaitingToStart);
+                client_ready = !(in_lobby && client_ready);
+                if let Some(c) = conn.as_ref() {
+                    c.set_ready(client_ready);
+                }
+            }
+            if let Some((sequence_number, input)) = input_manager.take_sent_input() {
+                session.predictor.on_input(sequence_number, input);
+            }
             if should_exit_input {
                 should_exit = true;
             }
         }
 
-        // Try to receive player_id (non-blocking)
-        while let Ok(id) = player_id_rx.try_recv() {
-            player_id = Some(id);
-            println!("Received player_id: {}", id);
-        }
-
-        // Try to receive new snapshots (non-blocking, drain all pending)
         let mut received_new_snapshot = false;
-        while let Ok(snap) = snapshot_rx.try_recv() {
-            latest_snapshot = Some(snap);
-            received_new_snapshot = true;
-            frames_without_update = 0;
+        if let Some(c) = conn.as_mut() {
+            // Try to receive player_id and world size (non-blocking)
+            while let Ok(welcome) = c.welcome_rx.try_recv() {
+                // Another room: nothing buffered or predicted carries over
+                if session.room.as_ref().is_some_and(|room| *room != welcome.room) {
+                    session = Session::new(interp_delay);
+                }
+                session.room = Some(welcome.room.clone());
+                session.epoch = welcome.epoch;
+                session.player_id = Some(welcome.player_id);
+                debug!(player = welcome.player_id, "Player id received");
+                resume_token = Some(welcome.resume_token.clone());
+                render_manager.set_room(&welcome.room);
+                apply_constants(&mut session, &mut render_manager, &welcome.constants);
+            }
+
+            // Admin announcements
+            while let Ok(message) = c.announcement_rx.try_recv() {
+                let until = Instant::now() + Duration::from_millis(message.duration_ms);
+                announcement = Some((message.text, until));
+            }
+
+            // Room list (start page) and refused room switches
+            while let Ok(list) = c.room_rx.try_recv() {
+                if list.error.is_some() {
+                    connect_error = list.error;
+                }
+                rooms = list.rooms;
+                render_manager.set_room(&list.current);
+            }
+            if !name_submitted && last_room_poll.elapsed() >= ROOM_LIST_INTERVAL {
+                c.list_rooms();
+                last_room_poll = Instant::now();
+            }
+
+            // Hall of fame (start page)
+            while let Ok(top) = c.hall_rx.try_recv() {
+                hall_of_fame = Some(top);
+            }
+            if !name_submitted && last_hall_of_fame_poll.elapsed() >= HALL_OF_FAME_INTERVAL {
+                c.hall_of_fame();
+                last_hall_of_fame_poll = Instant::now();
+            }
+
+            // Try to receive new snapshots (non-blocking, drain all pending)
+            while let Ok(snap) = c.snapshot_rx.try_recv() {
+                if snap.epoch != session.epoch {
+                    continue;
+                }
+                let constants_changed = session
+                    .latest_snapshot
+                    .as_ref()
+                    .is_some_and(|old| old.snapshot.constants != snap.snapshot.constants);
+                if constants_changed {
+                    debug!("Game constants changed");
+                    apply_constants(&mut session, &mut render_manager, &snap.snapshot.constants);
+                }
+                session.snapshot_buffer.push(snap.clone());
+                session.latest_snapshot = Some(snap);
+                received_new_snapshot = true;
+                session.frames_without_update = 0;
+            }
         }
 
         if !received_new_snapshot {
-            frames_without_update += 1;
+            session.frames_without_update += 1;
         }
 
+        // Reconcile the local prediction with the newest server state, or
+        // keep moving it between snapshots
+        match session.latest_snapshot.as_ref() {
+            Some(snap) if received_new_snapshot => session.predictor.on_snapshot(&snap.snapshot, session.player_id),
+            _ => session.predictor.advance(get_frame_time() * 1000.0),
+        }
+        let predicted = session.predictor.predicted();
+
         // Render the game
-        if let Some(ref snap) = latest_snapshot {
-            render_manager.render(&snap.snapshot, snap.received_at, player_id, client_ready, !name_submitted, &player_name, join_time);
+        let lobby = session
+            .latest_snapshot
+            .as_ref()
+            .filter(|s| s.snapshot.status == GameStatus::WaitingToStart)
+            .map(|s| s.snapshot.lobby.clone());
+        let start_page = StartPage {
+            show_name_input: !name_submitted,
+            player_name: &player_name,
+            server_addr: &server_addr,
+            editing_server,
+            message: connect_error.as_deref(),
+            rooms: &rooms,
+            lobby: lobby.as_ref(),
+            ready: client_ready,
+            hall_of_fame: hall_of_fame.as_ref(),
+            team_count: session.latest_snapshot.as_ref().map_or(0, |s| s.snapshot.constants.team_count),
+        };
+        // Remote players interpolated between buffered snapshots
+        let view = session.snapshot_buffer.sample(session.player_id);
+        if let Some(snap) = view.as_ref() {
+            render_manager.render(&snap.snapshot, snap.received_at, session.player_id, predicted, client_ready, &start_page, join_time);
             
             // Show warning if no updates for a while
-            if frames_without_update > 120 {
+            if session.frames_without_update > 120 {
                 draw_text(
                     "No updates from server...",
                     screen_width() / 2.0 - 120.0,
@@ -321,6 +837,13 @@ async fn main() {
                     Color::from_rgba(255, 200, 0, 255),
                 );
             }
+        } else if conn.is_none() {
+            // Not connected: start page with the server address field
+            clear_background(Color::from_rgba(10, 10, 15, 255));
+            render_manager.draw_start_page(screen_width(), screen_height(), &start_page);
+            if is_key_pressed(KeyCode::Escape) {
+                should_exit = true;
+            }
         } else {
             // Show connecting message
             clear_background(BLACK);
@@ -333,8 +856,17 @@ async fn main() {
             );
         }
 
+        // Server announcement banner
+        if let Some((text, until)) = announcement.as_ref() {
+            if Instant::now() < *until {
+                render_manager.draw_announcement(text);
+            } else {
+                announcement = None;
+            }
+        }
+
         // Show connection lost message overlay
-        if connection_lost {
+        if let Some(reconnect) = reconnect.as_ref() {
             let box_width = 400.0;
             let box_height = 100.0;
             let box_x = screen_width() / 2.0 - box_width / 2.0;
@@ -355,20 +887,22 @@ async fn main() {
                 30.0,
                 RED,
             );
+            draw_text(
+                &reconnect.status(),
+                screen_width() / 2.0 - 130.0,
+                screen_height() / 2.0 + 15.0,
+                20.0,
+                WHITE,
+            );
             draw_text(
                 "Press ESC to exit",
                 screen_width() / 2.0 - 80.0,
-                screen_height() / 2.0 + 25.0,
+                screen_height() / 2.0 + 38.0,
                 20.0,
                 WHITE,
             );
         }
 
-        // Check if tasks finished
-        if read_handle.is_finished() || write_handle.is_finished() {
-            should_exit = true;
-        }
-
         if should_exit {
             break;
         }
@@ -377,8 +911,12 @@ async fn main() {
     }
 
     // Cleanup
-    read_handle.abort();
-    write_handle.abort();
+    if let Some(c) = conn {
+        c.close();
+    }
+    if let Some(r) = reconnect {
+        r.cancel();
+    }
     
-    println!("Client shutting down...");
+    info!("Client shutting down...");
 }
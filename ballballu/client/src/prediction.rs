@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+use shared::{GameConstant, GameSnapshot, MovementMode};
+use shared::objects::PlayerSpec;
+use shared::mechanics::{start_step, steer_player, update_position};
+
+/// Movement input as the prediction replays it
+#[derive(Debug, Clone, Copy)]
+pub enum MoveInput {
+    Steer { dx: f32, dy: f32 },               // continuous: mouse offset (`ClientMessage::Input`)
+    Step { dx: f32, dy: f32, distance: f32 }, // discrete: one arrow key step (`ClientMessage::Move`)
+}
+
+/// Discrete step: (dx, dy, distance)
+type QueuedStep = (f32, f32, f32);
+
+/// Input sent to the server but not yet reflected in a snapshot
+struct PendingInput {
+    input: MoveInput,
+    sequence_number: u64,
+    sent_at: Instant,
+}
+
+/// Client-side prediction for the local player
+/// Own inputs are applied immediately with the shared movement code. When a
+/// snapshot arrives, the server state is taken as the new base and every
+/// input it has not applied yet (sequence number above
+/// `PlayerSpec::sequence_number`) is replayed on top of it.
+pub struct Predictor {
+    pending: VecDeque<PendingInput>,
+    steering: Option<(f32, f32)>,    // last steering input sent, keeps steering until replaced
+    queued_step: Option<QueuedStep>, // arrow key step waiting for the running one (like the server)
+    predicted: Option<PlayerSpec>,
+    constants: Option<GameConstant>,
+}
+
+impl Predictor {
+    pub fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            steering: None,
+            queued_step: None,
+            predicted: None,
+            constants: None,
+        }
+    }
+
+    /// World size, tick length and movement mode come from the WelcomeMessage
+    pub fn set_constants(&mut self, constants: GameConstant) {
+        if constants.movement_mode == MovementMode::Discrete {
+            self.steering = None;
+        }
+        self.queued_step = None;
+        self.constants = Some(constants);
+    }
+
+    /// Predicted local player, None until the first snapshot with our player
+    pub fn predicted(&self) -> Option<&PlayerSpec> {
+        self.predicted.as_ref()
+    }
+
+    /// Record an input that was just sent and apply it right away
+    pub fn on_input(&mut self, sequence_number: u64, input: MoveInput) {
+        match input {
+            MoveInput::Steer { dx, dy } => self.steering = Some((dx, dy)),
+            MoveInput::Step { dx, dy, distance } => {
+                self.queued_step = Some((dx, dy, distance));
+                if let Some(player) = self.predicted.as_mut() {
+                    start_queued_step(player, &mut self.queued_step);
+                }
+            }
+        }
+        self.pending.push_back(PendingInput { input, sequence_number, sent_at: Instant::now() });
+    }
+
+    /// Advance the prediction by one rendered frame
+    pub fn advance(&mut self, frame_ms: f32) {
+        if let (Some(player), Some(constants)) = (self.predicted.as_mut(), self.constants.as_ref()) {
+            simulate(player, self.steering, &mut self.queued_step, frame_ms, constants);
+        }
+    }
+
+    /// Reconcile with an authoritative snapshot
+    pub fn on_snapshot(&mut self, snapshot: &GameSnapshot, player_id: Option<u64>) {
+        let Some(server_player) = player_id.and_then(|id| snapshot.players.iter().find(|p| p.id == id)) else {
+            self.predicted = None;
+            return;
+        };
+        let Some(constants) = self.constants.as_ref() else {
+            return;
+        };
+
+        // Drop everything the server already took
+        let acked = server_player.sequence_number;
+        while self.pending.front().is_some_and(|p| p.sequence_number <= acked) {
+            self.pending.pop_front();
+        }
+
+        // Rewind to the server state and replay the rest, each input for as
+        // long as it was the newest one
+        let mut player = server_player.clone();
+        let mut queued_step = None;
+        let now = Instant::now();
+        for (i, pending) in self.pending.iter().enumerate() {
+            let until = self.pending.get(i + 1).map(|next| next.sent_at).unwrap_or(now);
+            let active_ms = until.saturating_duration_since(pending.sent_at).as_secs_f32() * 1000.0;
+            let steering = match pending.input {
+                MoveInput::Steer { dx, dy } => Some((dx, dy)),
+                MoveInput::Step { dx, dy, distance } => {
+                    queued_step = Some((dx, dy, distance));
+                    start_queued_step(&mut player, &mut queued_step);
+                    None
+                }
+            };
+            simulate(&mut player, steering, &mut queued_step, active_ms, constants);
+        }
+        self.queued_step = queued_step;
+        self.predicted = Some(player);
+    }
+}
+
+/// Run the server movement rule for `duration_ms`, re-steering or starting
+/// the queued step every tick like `GameState::apply_pending_moves` does
+fn simulate(
+    player: &mut PlayerSpec,
+    steering: Option<(f32, f32)>,
+    queued_step: &mut Option<QueuedStep>,
+    duration_ms: f32,
+    constants: &GameConstant,
+) {
+    let tick_ms = constants.tick_interval_ms as f32;
+    let mut left = duration_ms;
+    while left > 0.0 {
+        let step = left.min(tick_ms);
+        match steering {
+            Some((dx, dy)) => steer_player(player, dx, dy, tick_ms),
+            None => start_queued_step(player, queued_step),
+        }
+        let speed = player.speed;
+        update_position(player, speed, step, constants.world_width, constants.world_height);
+        left -= step;
+    }
+}
+
+/// Start the queued step once the running one has ended
+fn start_queued_step(player: &mut PlayerSpec, queued_step: &mut Option<QueuedStep>) {
+    if player.remaining_distance <= 0.0 {
+        if let Some((dx, dy, distance)) = queued_step.take() {
+            start_step(player, dx, dy, distance);
+        }
+    }
+}
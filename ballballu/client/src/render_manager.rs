@@ -1,12 +1,45 @@
 use macroquad::prelude::*;
-use shared::{GameSnapshot, GameStatus};
+use shared::{GameSnapshot, GameStatus, LobbyState, MAX_TEAMS};
+use shared::objects::PlayerSpec;
+use shared::protocol::{HallOfFameMessage, RoomInfo};
 use std::time::Instant;
 
+// World area shown on screen (adjust for zoom level)
+const VIEWPORT_WIDTH: f32 = 1000.0;
+const VIEWPORT_HEIGHT: f32 = 750.0;
+
+// Team mode: name and color of each team
+const TEAMS: [(&str, Color); MAX_TEAMS] = [
+    ("Red", Color::from_rgba(255, 90, 90, 255)),
+    ("Blue", Color::from_rgba(90, 140, 255, 255)),
+    ("Green", Color::from_rgba(90, 230, 90, 255)),
+    ("Yellow", Color::from_rgba(255, 240, 90, 255)),
+    ("Purple", Color::from_rgba(190, 110, 255, 255)),
+    ("Cyan", Color::from_rgba(90, 240, 240, 255)),
+    ("Orange", Color::from_rgba(255, 170, 60, 255)),
+    ("White", Color::from_rgba(235, 235, 235, 255)),
+];
+
+/// What the start page shows
+pub struct StartPage<'a> {
+    pub show_name_input: bool,
+    pub player_name: &'a str,
+    pub server_addr: &'a str,
+    pub editing_server: bool,      // server field has keyboard focus (TAB switches)
+    pub message: Option<&'a str>,  // e.g. why the last connection attempt failed
+    pub rooms: &'a [RoomInfo],     // rooms on the server (empty while not connected)
+    pub lobby: Option<&'a LobbyState>, // roster and countdown while the room waits to start
+    pub ready: bool,               // local player pressed Ready
+    pub team_count: usize,         // team mode: number keys pick a team in the lobby
+    pub hall_of_fame: Option<&'a HallOfFameMessage>, // best lives stored by the server
+}
+
 pub struct RenderManager {
     world_width: f32,
     world_height: f32,
     camera_x: f32,
     camera_y: f32,
+    room: Option<String>, // room the local player is in
 }
 
 impl RenderManager {
@@ -16,10 +49,24 @@ impl RenderManager {
             world_height,
             camera_x: world_width / 2.0,
             camera_y: world_height / 2.0,
+            room: None,
         }
     }
 
-    pub fn render(&mut self, snapshot: &GameSnapshot, received_at: Instant, player_id: Option<u64>, client_ready: bool, show_name_input: bool, player_name: &str, join_time: Option<Instant>) {
+    /// Resize the world used for the grid and boundary lines
+    pub fn set_world_size(&mut self, world_width: f32, world_height: f32) {
+        self.world_width = world_width;
+        self.world_height = world_height;
+    }
+
+    /// Room named in the HUD and highlighted in the start page room list
+    pub fn set_room(&mut self, room: &str) {
+        self.room = Some(room.to_string());
+    }
+
+    /// `predicted` replaces the local player from the snapshot (client prediction)
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(&mut self, snapshot: &GameSnapshot, received_at: Instant, player_id: Option<u64>, predicted: Option<&PlayerSpec>, client_ready: bool, start_page: &StartPage, join_time: Option<Instant>) {
         // Clear screen with dark background
         clear_background(Color::from_rgba(10, 10, 15, 255));
 
@@ -31,12 +78,12 @@ impl RenderManager {
         match snapshot.status {
             GameStatus::WaitingToStart => {
                 // Display start page
-                self.draw_start_page(screen_width, screen_height, show_name_input, player_name);
+                self.draw_start_page(screen_width, screen_height, start_page);
             }
             GameStatus::Playing => {
                 // If client hasn't pressed space yet, still show the start page
                 if !client_ready {
-                    self.draw_start_page(screen_width, screen_height, show_name_input, player_name);
+                    self.draw_start_page(screen_width, screen_height, start_page);
                 } else {
                     // Gameplay rendering
                     // Update camera to follow the local player (by player_id)
@@ -47,7 +94,11 @@ impl RenderManager {
                         snapshot.players.first()
                     };
                     
-                    if let Some(player) = player_to_follow {
+                    if let Some(player) = predicted {
+                        // Predicted state is already current
+                        self.camera_x = player.x;
+                        self.camera_y = player.y;
+                    } else if let Some(player) = player_to_follow {
                         // Use prediction for smooth camera movement
                         let pred_seconds = received_at.elapsed().as_secs_f32();
                         self.camera_x = player.x + player.vx * pred_seconds;
@@ -55,8 +106,8 @@ impl RenderManager {
                     }
 
                     // Calculate viewport bounds (world coordinates visible on screen)
-                    let viewport_width = 1000.0; // Adjust for zoom level
-                    let viewport_height = 750.0;
+                    let viewport_width = VIEWPORT_WIDTH;
+                    let viewport_height = VIEWPORT_HEIGHT;
                     let min_x = self.camera_x - viewport_width / 2.0;
                     let max_x = self.camera_x + viewport_width / 2.0;
                     let min_y = self.camera_y - viewport_height / 2.0;
@@ -97,19 +148,28 @@ impl RenderManager {
                         }
                     }
 
-                    // Draw players
+                    // Draw players (every cell; the label goes on the largest one)
                     for player in &snapshot.players {
-                        // Apply client-side prediction for smooth movement
-                        let pred_seconds = received_at.elapsed().as_secs_f32();
-                        let predicted_x = player.x + player.vx * pred_seconds;
-                        let predicted_y = player.y + player.vy * pred_seconds;
-
-                        // Check if player is in viewport
-                        if predicted_x >= min_x - player.radius
-                            && predicted_x <= max_x + player.radius
-                            && predicted_y >= min_y - player.radius
-                            && predicted_y <= max_y + player.radius
-                        {
+                        // Apply client-side prediction for smooth movement: the
+                        // local player comes from the predictor, others are extrapolated
+                        let (player, pred_seconds) = match predicted {
+                            Some(local) if local.id == player.id => (local, 0.0),
+                            _ => (player, received_at.elapsed().as_secs_f32()),
+                        };
+                        let player_color = Self::get_player_color(player.id, player.team);
+
+                        for cell in &player.cells {
+                            let predicted_x = cell.x + player.vx * pred_seconds;
+                            let predicted_y = cell.y + player.vy * pred_seconds;
+
+                            // Check if cell is in viewport
+                            if predicted_x < min_x - cell.radius
+                                || predicted_x > max_x + cell.radius
+                                || predicted_y < min_y - cell.radius
+                                || predicted_y > max_y + cell.radius
+                            {
+                                continue;
+                            }
                             let (screen_x, screen_y) = self.world_to_screen(
                                 predicted_x,
                                 predicted_y,
@@ -120,14 +180,12 @@ impl RenderManager {
                                 screen_width,
                                 screen_height,
                             );
-                            let screen_radius = self.world_to_screen_size(player.radius, viewport_width, screen_width);
+                            let screen_radius = self.world_to_screen_size(cell.radius, viewport_width, screen_width);
 
-                            let player_color = Self::get_player_color(player.id);
-
-                            // Draw player circle (filled)
+                            // Draw cell circle (filled)
                             draw_circle(screen_x, screen_y, screen_radius.max(5.0), player_color);
 
-                            // Draw player outline
+                            // Draw cell outline
                             draw_circle_lines(
                                 screen_x,
                                 screen_y,
@@ -135,6 +193,31 @@ impl RenderManager {
                                 2.0,
                                 Color::from_rgba(255, 255, 255, 120),
                             );
+                        }
+
+                        let Some(largest) = player.cells.iter().max_by(|a, b| a.radius.total_cmp(&b.radius)) else {
+                            continue;
+                        };
+                        let predicted_x = largest.x + player.vx * pred_seconds;
+                        let predicted_y = largest.y + player.vy * pred_seconds;
+
+                        // Check if the labelled cell is in viewport
+                        if predicted_x >= min_x - largest.radius
+                            && predicted_x <= max_x + largest.radius
+                            && predicted_y >= min_y - largest.radius
+                            && predicted_y <= max_y + largest.radius
+                        {
+                            let (screen_x, screen_y) = self.world_to_screen(
+                                predicted_x,
+                                predicted_y,
+                                min_x,
+                                max_x,
+                                min_y,
+                                max_y,
+                                screen_width,
+                                screen_height,
+                            );
+                            let screen_radius = self.world_to_screen_size(largest.radius, viewport_width, screen_width);
 
                             // Draw player name and score stacked above the circle (avoid overlap as radius grows)
                             let display_name = if player.name.trim().is_empty() {
@@ -142,6 +225,7 @@ impl RenderManager {
                             } else {
                                 player.name.clone()
                             };
+                            let display_name = if player.bot { format!("[BOT] {}", display_name) } else { display_name };
 
                             // Text sizes
                             let name_text_size = 20u16;
@@ -184,6 +268,31 @@ impl RenderManager {
                         }
                     }
 
+                    // Draw viruses on top of players so small cells can hide behind them
+                    let pred_seconds = received_at.elapsed().as_secs_f32();
+                    for virus in &snapshot.viruses {
+                        let predicted_x = virus.x + virus.vx * pred_seconds;
+                        let predicted_y = virus.y + virus.vy * pred_seconds;
+                        if predicted_x >= min_x - virus.radius
+                            && predicted_x <= max_x + virus.radius
+                            && predicted_y >= min_y - virus.radius
+                            && predicted_y <= max_y + virus.radius
+                        {
+                            let (screen_x, screen_y) = self.world_to_screen(
+                                predicted_x,
+                                predicted_y,
+                                min_x,
+                                max_x,
+                                min_y,
+                                max_y,
+                                screen_width,
+                                screen_height,
+                            );
+                            let screen_radius = self.world_to_screen_size(virus.radius, viewport_width, screen_width);
+                            Self::draw_virus(screen_x, screen_y, screen_radius);
+                        }
+                    }
+
                     // Draw UI overlay
                     self.draw_ui_overlay(snapshot,player_id, join_time);
 
@@ -195,14 +304,26 @@ impl RenderManager {
                     }
                 }
             }
-            // May not be used for argio style game
             GameStatus::GameOver => {
-                // Display game over screen
-                self.draw_game_over_page(screen_width, screen_height);
+                // Round results (start page for clients that have not joined yet)
+                if !client_ready {
+                    self.draw_start_page(screen_width, screen_height, start_page);
+                } else {
+                    self.draw_game_over_page(screen_width, screen_height, snapshot, player_id);
+                }
             }
         }
     }
 
+    /// World offset from the camera center (the local player) to a screen point
+    pub fn screen_to_world_offset(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        (
+            (screen_x - screen_width() / 2.0) / screen_width() * VIEWPORT_WIDTH,
+            (screen_y - screen_height() / 2.0) / screen_height() * VIEWPORT_HEIGHT,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn world_to_screen(
         &self,
         world_x: f32,
@@ -327,13 +448,28 @@ impl RenderManager {
             .map(|p| p.score)
             .unwrap_or(0);
 
-        // top 3 leaderboard
-        let mut players = snapshot.players.clone();
-        players.sort_by(|a, b| b.score.cmp(&a.score));
-        let top3 = players.into_iter().take(3).collect::<Vec<_>>();
+        // top 3 leaderboard (global summary from the server, not just visible players)
+        let top3 = snapshot.leaderboard.iter().take(3).collect::<Vec<_>>();
+
+        // round time left (None: no time limit)
+        let round_left_ms = snapshot
+            .round
+            .ends_at_tick
+            .map(|end| end.saturating_sub(snapshot.tick) * snapshot.constants.tick_interval_ms);
+
+        // team mode: own team and the team standings
+        let local_team = player_id
+            .and_then(|id| snapshot.players.iter().find(|p| p.id == id))
+            .and_then(|p| p.team);
+        let teams_h = if snapshot.teams.is_empty() {
+            0.0
+        } else {
+            line + 20.0 + (1 + snapshot.teams.len()) as f32 * line
+        };
 
         // panel height
-        let panel_h = 120.0 + top3.len() as f32 * line;
+        let round_h = if round_left_ms.is_some() { line } else { 0.0 };
+        let panel_h = 145.0 + round_h + teams_h + top3.len() as f32 * line;
 
         // background box
         draw_rectangle(0.0, 0.0, 260.0, panel_h, Color::from_rgba(0, 0, 0, 180));
@@ -364,6 +500,36 @@ impl RenderManager {
         );
         y += line;
 
+        // room
+        draw_text(
+            &format!("Room: {}", self.room.as_deref().unwrap_or("-")),
+            padding,
+            y + 20.0,
+            20.0,
+            YELLOW,
+        );
+        y += line;
+
+        if !snapshot.teams.is_empty() {
+            let (name, color) = local_team.and_then(|t| TEAMS.get(t as usize)).copied().unwrap_or(("-", YELLOW));
+            draw_text(&format!("Team: {}", name), padding, y + 20.0, 20.0, color);
+            y += line;
+        }
+
+        // round timer, red for the last 10 seconds
+        if let Some(left_ms) = round_left_ms {
+            let secs = left_ms.div_ceil(1000);
+            let color = if secs <= 10 { Color::from_rgba(255, 100, 100, 255) } else { YELLOW };
+            draw_text(
+                &format!("Round {} ends in {}:{:02}", snapshot.round.number, secs / 60, secs % 60),
+                padding,
+                y + 20.0,
+                20.0,
+                color,
+            );
+            y += line;
+        }
+
         // separator
         draw_line(padding, y + 10.0, 240.0, y + 10.0, 1.0, Color::from_rgba(120, 120, 120, 255));
         y += 20.0;
@@ -373,9 +539,10 @@ impl RenderManager {
 
         // top 3 entries
         for (i, p) in top3.iter().enumerate() {
-            let color = Self::get_player_color(p.id);
+            let color = Self::get_player_color(p.id, p.team);
 
             let name = if p.name.is_empty() { "Anonymous" } else { p.name.as_str() };
+            let name = if p.bot { format!("[BOT] {}", name) } else { name.to_string() };
 
             draw_text(
                 &format!("{}. {} (S:{})", i + 1, name, p.score),
@@ -386,11 +553,30 @@ impl RenderManager {
             );
             y += line;
         }
+
+        // team standings
+        if !snapshot.teams.is_empty() {
+            draw_line(padding, y + 10.0, 240.0, y + 10.0, 1.0, Color::from_rgba(120, 120, 120, 255));
+            y += 20.0;
+            draw_text("Teams:", padding, y + 20.0, 18.0, WHITE);
+            y += line;
+            for (i, t) in snapshot.teams.iter().enumerate() {
+                let (name, color) = TEAMS.get(t.team as usize).copied().unwrap_or(("?", WHITE));
+                draw_text(
+                    &format!("{}. {} (S:{}, {}P)", i + 1, name, t.score, t.players),
+                    padding,
+                    y + 20.0,
+                    18.0,
+                    color,
+                );
+                y += line;
+            }
+        }
     }
 
     fn draw_controls_panel(&self) {
         let w = 260.0;
-        let h = 90.0;
+        let h = 130.0;
         let x = 10.0;
         let y = screen_height() - h - 10.0;
 
@@ -406,26 +592,70 @@ impl RenderManager {
             Color::from_rgba(200, 200, 200, 255),
         );
 
-        // WASD
+        // Arrows
         draw_text(
-            "WASD / Arrow Keys - Move",
+            "Arrow Keys - Move",
             x + 10.0,
             y + 50.0,
             16.0,
             WHITE,
         );
 
+        // Split
+        draw_text(
+            "SPACE - Split",
+            x + 10.0,
+            y + 70.0,
+            16.0,
+            WHITE,
+        );
+
+        // Eject
+        draw_text(
+            "W - Eject Mass",
+            x + 10.0,
+            y + 90.0,
+            16.0,
+            WHITE,
+        );
+
         // Quit
         draw_text(
             "ESC - Quit",
             x + 10.0,
-            y + 70.0,
+            y + 110.0,
             16.0,
             WHITE,
         );
     }
 
-    fn get_player_color(player_id: u64) -> Color {
+    /// Green circle with a ring of spikes
+    fn draw_virus(x: f32, y: f32, radius: f32) {
+        let spikes = 20;
+        let fill = Color::from_rgba(60, 220, 60, 230);
+        let edge = Color::from_rgba(30, 140, 30, 255);
+        let spike_len = radius * 0.15;
+
+        for i in 0..spikes {
+            let a0 = i as f32 / spikes as f32 * std::f32::consts::TAU;
+            let a1 = (i as f32 + 0.5) / spikes as f32 * std::f32::consts::TAU;
+            let a2 = (i as f32 + 1.0) / spikes as f32 * std::f32::consts::TAU;
+            draw_triangle(
+                vec2(x + a0.cos() * radius, y + a0.sin() * radius),
+                vec2(x + a1.cos() * (radius + spike_len), y + a1.sin() * (radius + spike_len)),
+                vec2(x + a2.cos() * radius, y + a2.sin() * radius),
+                edge,
+            );
+        }
+        draw_circle(x, y, radius, fill);
+        draw_circle_lines(x, y, radius, 2.0, edge);
+    }
+
+    /// Team color in team mode, otherwise one of a few colors by player id
+    fn get_player_color(player_id: u64, team: Option<u8>) -> Color {
+        if let Some((_, color)) = team.and_then(|team| TEAMS.get(team as usize)) {
+            return *color;
+        }
         let colors = [
             Color::from_rgba(255, 100, 100, 255), // Red
             Color::from_rgba(100, 150, 255, 255), // Blue
@@ -438,7 +668,11 @@ impl RenderManager {
         colors[(player_id as usize) % colors.len()]
     }
 
-    fn draw_start_page(&self, screen_width: f32, screen_height: f32, show_name_input: bool, player_name: &str) {
+    pub fn draw_start_page(&self, screen_width: f32, screen_height: f32, start_page: &StartPage) {
+        let player_name = start_page.player_name;
+        let focused = Color::from_rgba(255, 200, 100, 255);
+        let unfocused = Color::from_rgba(200, 200, 200, 255);
+
         // Draw semi-transparent overlay
         draw_rectangle(0.0, 0.0, screen_width, screen_height, Color::from_rgba(0, 0, 0, 200));
 
@@ -447,7 +681,7 @@ impl RenderManager {
         let title_size = 80.0;
         let title_dims = measure_text(title, None, title_size as u16, 1.0);
         let title_x = screen_width / 2.0 - title_dims.width / 2.0;
-        let title_y = screen_height / 2.0 - 120.0;
+        let title_y = screen_height / 2.0 - 200.0;
         draw_text(title, title_x, title_y, title_size, Color::from_rgba(100, 255, 100, 255));
 
         // Draw subtitle
@@ -459,7 +693,7 @@ impl RenderManager {
         draw_text(subtitle, subtitle_x, subtitle_y, subtitle_size, Color::from_rgba(150, 150, 255, 255));
 
         // Draw author information
-        let authors = vec![
+        let authors = [
             "Litao(John) Zhou - 1006013092",
             "Siyu Shao - 1007147204",
             "Chuyue Zhang - 1005728303",
@@ -475,7 +709,7 @@ impl RenderManager {
         }
 
         // Draw player name input box
-        if show_name_input {
+        if start_page.show_name_input {
             let input_y = author_y_start + (authors.len() as f32 * 25.0) + 40.0;
             
             // Draw label
@@ -491,7 +725,8 @@ impl RenderManager {
             let box_x = screen_width / 2.0 - box_width / 2.0;
             let box_y = input_y + 35.0;
             draw_rectangle(box_x, box_y, box_width, box_height, Color::from_rgba(50, 50, 50, 255));
-            draw_rectangle_lines(box_x, box_y, box_width, box_height, 2.0, Color::from_rgba(200, 200, 200, 255));
+            let name_border = if start_page.editing_server { unfocused } else { focused };
+            draw_rectangle_lines(box_x, box_y, box_width, box_height, 2.0, name_border);
             
             // Draw typed text
             let text_size = 24.0;
@@ -503,10 +738,30 @@ impl RenderManager {
             let char_count_dims = measure_text(&char_count_text, None, char_count_size as u16, 1.0);
             let char_count_x = screen_width / 2.0 - char_count_dims.width / 2.0;
             draw_text(&char_count_text, char_count_x, box_y + box_height + 25.0, char_count_size, Color::from_rgba(150, 150, 150, 255));
+
+            // Draw server address field below (TAB switches fields)
+            let server_label = "Server Address (TAB to edit):";
+            let server_label_y = box_y + box_height + 60.0;
+            let server_label_dims = measure_text(server_label, None, label_size as u16, 1.0);
+            draw_text(server_label, screen_width / 2.0 - server_label_dims.width / 2.0, server_label_y, label_size, unfocused);
+
+            let server_box_height = 34.0;
+            let server_box_y = server_label_y + 15.0;
+            draw_rectangle(box_x, server_box_y, box_width, server_box_height, Color::from_rgba(50, 50, 50, 255));
+            let server_border = if start_page.editing_server { focused } else { unfocused };
+            draw_rectangle_lines(box_x, server_box_y, box_width, server_box_height, 2.0, server_border);
+            draw_text(start_page.server_addr, box_x + 10.0, server_box_y + 24.0, 20.0, WHITE);
         }
 
-        // Instruction text depends on whether name is empty or not
-        let instruction = if player_name.is_empty() {
+        // Instruction text depends on whether name is empty or not (or the ready state in the lobby)
+        let lobby = start_page.lobby.filter(|_| !start_page.show_name_input);
+        let instruction = if lobby.is_some() {
+            if start_page.ready {
+                "Ready! Press ENTER to cancel"
+            } else {
+                "Press ENTER when ready"
+            }
+        } else if player_name.is_empty() {
             "Press ENTER to start as Anonymous"
         } else {
             "Press ENTER to continue"
@@ -516,12 +771,12 @@ impl RenderManager {
         let instruction_dims = measure_text(instruction, None, instruction_size as u16, 1.0);
         let instruction_x = screen_width / 2.0 - instruction_dims.width / 2.0;
 
-        // Place instruction below input box (input box bottom = box_y + box_height)
+        // Place instruction below the server box (name box + char count + server field)
         let instruction_y = {
             let authors_height = authors.len() as f32 * 25.0;
             let input_y = author_y_start + authors_height + 40.0;
             let box_y = input_y + 35.0;
-            box_y + 40.0 + 70.0  // box height + padding
+            box_y + 40.0 + 60.0 + 15.0 + 34.0 + 50.0  // boxes + padding
         };
 
         draw_text(
@@ -532,6 +787,117 @@ impl RenderManager {
             Color::from_rgba(255, 200, 100, 255),
         );
 
+        // Lobby status above the instruction
+        if let Some(lobby) = lobby {
+            let status = match lobby.countdown_ms {
+                Some(ms) => format!("Starting in {}...", ms.div_ceil(1000)),
+                None if lobby.roster.len() < lobby.min_players => {
+                    format!("Waiting for players ({}/{})", lobby.roster.len(), lobby.min_players)
+                }
+                None => "Waiting for everyone to be ready".to_string(),
+            };
+            let status_size = 28.0;
+            let status_dims = measure_text(&status, None, status_size as u16, 1.0);
+            let status_x = screen_width / 2.0 - status_dims.width / 2.0;
+            draw_text(&status, status_x, instruction_y - 60.0, status_size, WHITE);
+
+            // Team mode: number keys switch teams
+            if start_page.team_count > 0 {
+                let hint = format!("Press 1-{} to switch team", start_page.team_count);
+                let hint_dims = measure_text(&hint, None, 20, 1.0);
+                draw_text(&hint, screen_width / 2.0 - hint_dims.width / 2.0, instruction_y - 30.0, 20.0, unfocused);
+            }
+        }
+
+        // Connection problems
+        if let Some(message) = start_page.message {
+            let message_size = 20.0;
+            let message_dims = measure_text(message, None, message_size as u16, 1.0);
+            let message_x = screen_width / 2.0 - message_dims.width / 2.0;
+            draw_text(message, message_x, instruction_y + 35.0, message_size, Color::from_rgba(255, 120, 120, 255));
+        }
+
+        // Room list in the top right corner
+        if !start_page.rooms.is_empty() {
+            let panel_w = 260.0;
+            let row = 22.0;
+            let panel_x = screen_width - panel_w - 20.0;
+            let panel_y = 20.0;
+            let panel_h = 40.0 + start_page.rooms.len() as f32 * row;
+            draw_rectangle(panel_x, panel_y, panel_w, panel_h, Color::from_rgba(0, 0, 0, 180));
+            draw_text("Rooms:", panel_x + 10.0, panel_y + 26.0, 20.0, unfocused);
+            for (i, room) in start_page.rooms.iter().enumerate() {
+                let current = self.room.as_deref() == Some(room.name.as_str());
+                let color = if current {
+                    focused
+                } else if room.players >= room.max_players {
+                    Color::from_rgba(120, 120, 120, 255)
+                } else {
+                    WHITE
+                };
+                let y = panel_y + 50.0 + i as f32 * row;
+                let marker = if current { ">" } else { " " };
+                draw_text(&format!("{} {}", marker, room.name), panel_x + 10.0, y, 18.0, color);
+                let count = format!("{}/{}", room.players, room.max_players);
+                let count_dims = measure_text(&count, None, 18, 1.0);
+                draw_text(&count, panel_x + panel_w - count_dims.width - 10.0, y, 18.0, color);
+            }
+        }
+
+        // Lobby roster in the top left corner
+        if let Some(lobby) = start_page.lobby.filter(|l| !l.roster.is_empty()) {
+            let panel_w = 260.0;
+            let row = 22.0;
+            let panel_x = 20.0;
+            let panel_y = 20.0;
+            let panel_h = 40.0 + lobby.roster.len() as f32 * row;
+            draw_rectangle(panel_x, panel_y, panel_w, panel_h, Color::from_rgba(0, 0, 0, 180));
+            draw_text("Lobby:", panel_x + 10.0, panel_y + 26.0, 20.0, unfocused);
+            for (i, entry) in lobby.roster.iter().enumerate() {
+                let y = panel_y + 50.0 + i as f32 * row;
+                let name = if entry.name.is_empty() { "Anonymous" } else { entry.name.as_str() };
+                draw_text(name, panel_x + 10.0, y, 18.0, Self::get_player_color(entry.id, entry.team));
+                let (state, color) = if entry.ready {
+                    ("READY", Color::from_rgba(100, 255, 100, 255))
+                } else {
+                    ("waiting", Color::from_rgba(150, 150, 150, 255))
+                };
+                let state_dims = measure_text(state, None, 18, 1.0);
+                draw_text(state, panel_x + panel_w - state_dims.width - 10.0, y, 18.0, color);
+            }
+        }
+
+        // Hall of fame in the bottom left corner
+        if let Some(hall_of_fame) = start_page.hall_of_fame.filter(|h| !h.all_time.is_empty()) {
+            const SHOWN: usize = 5;
+            let lists = [("Today:", &hall_of_fame.daily), ("All time:", &hall_of_fame.all_time)];
+            let row = 22.0;
+            let rows: usize = lists.iter().map(|(_, list)| 1 + list.len().clamp(1, SHOWN)).sum();
+            let panel_w = 300.0;
+            let panel_h = 40.0 + rows as f32 * row;
+            let panel_x = 20.0;
+            let panel_y = screen_height - panel_h - 20.0;
+            draw_rectangle(panel_x, panel_y, panel_w, panel_h, Color::from_rgba(0, 0, 0, 180));
+            draw_text("Hall of Fame", panel_x + 10.0, panel_y + 26.0, 20.0, focused);
+            let mut y = panel_y + 50.0;
+            for (title, list) in lists {
+                draw_text(title, panel_x + 10.0, y, 18.0, unfocused);
+                y += row;
+                if list.is_empty() {
+                    draw_text("  -", panel_x + 10.0, y, 18.0, GRAY);
+                    y += row;
+                }
+                for (i, entry) in list.iter().take(SHOWN).enumerate() {
+                    let name = if entry.name.is_empty() { "Anonymous" } else { entry.name.as_str() };
+                    draw_text(&format!("{}. {}", i + 1, name), panel_x + 20.0, y, 18.0, WHITE);
+                    let score = entry.peak_score.to_string();
+                    let score_dims = measure_text(&score, None, 18, 1.0);
+                    draw_text(&score, panel_x + panel_w - score_dims.width - 10.0, y, 18.0, WHITE);
+                    y += row;
+                }
+            }
+        }
+
         // (Removed controls from start page)
         // Draw controls hint
         // let controls = "Use WASD or Arrow Keys to move\nESC to quit";
@@ -540,25 +906,95 @@ impl RenderManager {
         // draw_text(controls, 20.0, controls_y, controls_size, Color::from_rgba(200, 200, 200, 200));
     }
 
-    // May not be used for argio style game
-    fn draw_game_over_page(&self, screen_width: f32, screen_height: f32) {
+    /// Round results: ranked players, the local player's stats and the time
+    /// until the next round
+    fn draw_game_over_page(&self, screen_width: f32, screen_height: f32, snapshot: &GameSnapshot, player_id: Option<u64>) {
+        let round = &snapshot.round;
+
         // Draw semi-transparent overlay
         draw_rectangle(0.0, 0.0, screen_width, screen_height, Color::from_rgba(0, 0, 0, 200));
 
         // Draw title
-        let title = "GAME OVER";
-        let title_size = 80.0;
-        let title_dims = measure_text(title, None, title_size as u16, 1.0);
+        let title = format!("ROUND {} OVER", round.number);
+        let title_size = 70.0;
+        let title_dims = measure_text(&title, None, title_size as u16, 1.0);
         let title_x = screen_width / 2.0 - title_dims.width / 2.0;
-        let title_y = screen_height / 2.0 - 60.0;
-        draw_text(title, title_x, title_y, title_size, Color::from_rgba(255, 100, 100, 255));
-
-        // Draw restart instruction
-        let restart = "Press ENTER to Return to Start";
-        let restart_size = 30.0;
-        let restart_dims = measure_text(restart, None, restart_size as u16, 1.0);
-        let restart_x = screen_width / 2.0 - restart_dims.width / 2.0;
-        let restart_y = screen_height / 2.0 + 60.0;
-        draw_text(restart, restart_x, restart_y, restart_size, Color::from_rgba(255, 200, 100, 255));
+        let title_y = 110.0;
+        draw_text(&title, title_x, title_y, title_size, Color::from_rgba(255, 100, 100, 255));
+
+        // Placements: top 10, plus the local player if further down
+        let row = 28.0;
+        let table_w = 520.0;
+        let table_x = screen_width / 2.0 - table_w / 2.0;
+        let mut y = title_y + 60.0;
+        draw_text("#", table_x, y, 20.0, GRAY);
+        draw_text("Player", table_x + 50.0, y, 20.0, GRAY);
+        draw_text("Score", table_x + 330.0, y, 20.0, GRAY);
+        draw_text("Peak", table_x + 430.0, y, 20.0, GRAY);
+        y += row;
+        let mine = player_id.and_then(|id| round.results.iter().find(|r| r.id == id));
+        let shown = round
+            .results
+            .iter()
+            .take(10)
+            .chain(mine.filter(|r| r.rank > 10));
+        for result in shown {
+            let is_me = Some(result.id) == player_id;
+            let color = if is_me { Color::from_rgba(255, 200, 100, 255) } else { Self::get_player_color(result.id, result.team) };
+            let name = if result.name.is_empty() { "Anonymous" } else { result.name.as_str() };
+            let name = if result.bot { format!("[BOT] {}", name) } else { name.to_string() };
+            draw_text(&result.rank.to_string(), table_x, y, 22.0, color);
+            draw_text(&name, table_x + 50.0, y, 22.0, color);
+            draw_text(&result.score.to_string(), table_x + 330.0, y, 22.0, color);
+            draw_text(&result.peak_score.to_string(), table_x + 430.0, y, 22.0, color);
+            y += row;
+        }
+
+        // Personal stats
+        if let Some(me) = mine {
+            y += 20.0;
+            let lines = [
+                format!("You placed #{} of {}", me.rank, round.results.len()),
+                format!(
+                    "Dots eaten: {}   Cells eaten: {}   Times eaten: {}",
+                    me.dots_eaten, me.cells_eaten, me.times_eaten
+                ),
+            ];
+            for (i, text) in lines.iter().enumerate() {
+                let size = if i == 0 { 30.0 } else { 22.0 };
+                let dims = measure_text(text, None, size as u16, 1.0);
+                draw_text(text, screen_width / 2.0 - dims.width / 2.0, y, size, WHITE);
+                y += 34.0;
+            }
+        }
+
+        // Time until the world resets
+        if let Some(next) = round.next_round_at_tick {
+            let secs = (next.saturating_sub(snapshot.tick) * snapshot.constants.tick_interval_ms).div_ceil(1000);
+            let text = format!("Next round in {}...", secs);
+            let size = 30.0;
+            let dims = measure_text(&text, None, size as u16, 1.0);
+            let text_y = (y + 30.0).max(screen_height - 60.0);
+            draw_text(&text, screen_width / 2.0 - dims.width / 2.0, text_y, size, Color::from_rgba(255, 200, 100, 255));
+        }
+    }
+
+    /// Server announcement banner across the top of the screen
+    pub fn draw_announcement(&self, text: &str) {
+        let size = 26.0;
+        let dims = measure_text(text, None, size as u16, 1.0);
+        let w = (dims.width + 40.0).min(screen_width() - 20.0);
+        let h = 44.0;
+        let x = screen_width() / 2.0 - w / 2.0;
+        let y = 50.0;
+        draw_rectangle(x, y, w, h, Color::from_rgba(20, 20, 40, 230));
+        draw_rectangle_lines(x, y, w, h, 2.0, Color::from_rgba(255, 200, 100, 255));
+        draw_text(
+            text,
+            screen_width() / 2.0 - dims.width / 2.0,
+            y + h / 2.0 + dims.offset_y / 2.0,
+            size,
+            Color::from_rgba(255, 200, 100, 255),
+        );
     }
 }
@@ -1,8 +1,57 @@
+use std::collections::VecDeque;
 use std::time::Instant;
 use shared::GameSnapshot;
+use shared::delta::{self, SnapshotDelta};
 
 #[derive(Clone)]
 pub struct ClientSnapshot {
     pub snapshot: GameSnapshot,
     pub received_at: Instant,
-}
\ No newline at end of file
+    pub epoch: u64, // of the Welcome it came after; older ones belong to a world we left
+}
+
+/// Rebuilds full snapshots from keyframes + deltas.
+/// Keeps the last few snapshots because the server diffs against
+/// whichever tick we acknowledged last, which may lag behind by the RTT.
+pub struct SnapshotAssembler {
+    recent: VecDeque<GameSnapshot>,
+    capacity: usize,
+}
+
+impl SnapshotAssembler {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            recent: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Forget every snapshot (the connection moved to another world)
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Store a full snapshot (keyframe)
+    pub fn on_full(&mut self, snapshot: GameSnapshot) -> GameSnapshot {
+        self.remember(snapshot.clone());
+        snapshot
+    }
+
+    /// Apply a delta; None if its base snapshot is unknown (wait for next keyframe)
+    pub fn on_delta(&mut self, delta: &SnapshotDelta) -> Option<GameSnapshot> {
+        let base = self.recent.iter().find(|s| s.tick == delta.base_tick)?;
+        let snapshot = delta::apply(base, delta);
+        self.remember(snapshot.clone());
+        Some(snapshot)
+    }
+
+    fn remember(&mut self, snapshot: GameSnapshot) {
+        // A keyframe from an older tick than what we hold means the server restarted
+        // the sequence; drop everything newer so bases stay consistent
+        self.recent.retain(|s| s.tick < snapshot.tick);
+        self.recent.push_back(snapshot);
+        while self.recent.len() > self.capacity {
+            self.recent.pop_front();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Tick benchmark: 200 players and 5,000 dots
+//! Run with `cargo bench -p server --bench tick`
+//!
+//! Measures `run_tick` (movement + grid-based collisions) and, for
+//! comparison, the brute-force O(players x dots) + O(players^2) collision
+//! scan the game loop used before the spatial grid.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+use server::config::GameConfig;
+use server::game_loop::run_tick;
+use server::game_state::GameState;
+use shared::GameStatus;
+use shared::mechanics::{cells_collisions_check, dot_collision_check};
+
+const PLAYERS: u64 = 200;
+const DOTS: usize = 5_000;
+const WARMUP_TICKS: usize = 20;
+const MEASURED_TICKS: usize = 200;
+
+fn build_world() -> GameState {
+    let config = GameConfig {
+        world_width: 5000.0,
+        world_height: 5000.0,
+        initial_dot_count: DOTS,
+        ..GameConfig::default()
+    };
+    let mut gs = GameState::new(config);
+    for id in 1..=PLAYERS {
+        gs.add_player(id);
+    }
+    gs.status = GameStatus::Playing;
+    gs
+}
+
+/// Give every player a random step, like clients holding WASD
+fn queue_random_moves(gs: &mut GameState, rng: &mut impl Rng) {
+    for id in 1..=PLAYERS {
+        let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        gs.queue_move(id, angle.cos(), angle.sin(), 40.0);
+    }
+}
+
+/// The pre-grid collision scan (checks only, no side effects)
+fn brute_force_collisions(gs: &GameState) -> usize {
+    let mut hits = 0;
+    let cells: Vec<_> = gs.players.values().flat_map(|p| p.cells.iter()).collect();
+    for c in &cells {
+        for d in gs.dots.values() {
+            if dot_collision_check(c, d) {
+                hits += 1;
+            }
+        }
+    }
+    for i in 0..cells.len() {
+        for j in (i + 1)..cells.len() {
+            if cells_collisions_check(cells[i], cells[j]) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+fn report(name: &str, mut samples: Vec<Duration>) {
+    samples.sort();
+    let total: Duration = samples.iter().sum();
+    let mean = total / samples.len() as u32;
+    let pct = |p: f64| samples[((samples.len() - 1) as f64 * p) as usize];
+    println!(
+        "{:<28} mean {:>9.3?}  p50 {:>9.3?}  p99 {:>9.3?}  max {:>9.3?}",
+        name,
+        mean,
+        pct(0.50),
+        pct(0.99),
+        samples[samples.len() - 1],
+    );
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+
+    let setup_start = Instant::now();
+    let mut gs = build_world();
+    println!(
+        "world: {} players, {} dots, {}x{} (setup {:.1?})",
+        gs.players.len(),
+        gs.dots.len(),
+        gs.constants.world_width,
+        gs.constants.world_height,
+        setup_start.elapsed(),
+    );
+
+    for _ in 0..WARMUP_TICKS {
+        queue_random_moves(&mut gs, &mut rng);
+        run_tick(&mut gs);
+    }
+
+    let mut tick_samples = Vec::with_capacity(MEASURED_TICKS);
+    let mut brute_samples = Vec::with_capacity(MEASURED_TICKS);
+    for _ in 0..MEASURED_TICKS {
+        queue_random_moves(&mut gs, &mut rng);
+
+        let start = Instant::now();
+        run_tick(&mut gs);
+        tick_samples.push(start.elapsed());
+
+        let start = Instant::now();
+        black_box(brute_force_collisions(black_box(&gs)));
+        brute_samples.push(start.elapsed());
+    }
+
+    println!();
+    report("run_tick (spatial grid)", tick_samples);
+    report("brute-force collision scan", brute_samples);
+    println!(
+        "tick budget at {} ms/tick: {:?}",
+        gs.constants.tick_interval_ms,
+        Duration::from_millis(gs.constants.tick_interval_ms),
+    );
+}
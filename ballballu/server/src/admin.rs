@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::info;
+
+use shared::GameStatus;
+use shared::protocol::{AnnouncementMessage, ServerMessage};
+use crate::room_manager::{Room, RoomManager};
+
+/// Bye reason for clients refused or dropped because of a ban
+pub const BAN_REASON: &str = "You are banned from this server";
+const KICK_REASON: &str = "Kicked by the server admin";
+// How long `say` banners stay on screen
+const ANNOUNCEMENT_MS: u64 = 8_000;
+// Upper limit for one `dots` command
+const MAX_SPAWN_DOTS: usize = 10_000;
+// Settings the tick task or the world layout depend on; they need a restart
+const RESTART_SETTINGS: [&str; 4] = ["tick_interval_ms", "world_width", "world_height", "team_count"];
+
+const HELP: &str = "\
+Commands (a room is its id or name, see `rooms`):
+  rooms                           list rooms
+  players [room]                  list players with score and address
+  kick <player-id> [reason]       disconnect a player
+  ban ip <address>                refuse (and drop) clients from an address
+  ban name <name>                 refuse (and drop) players with a name
+  unban ip <address>
+  unban name <name>
+  bans                            list bans
+  status <room> <lobby|playing|gameover>
+  reset <room>                    fresh world, back to the lobby
+  set <room> <setting> <value>    change a [game] setting of a running room
+  dots <room> <count>             spawn extra dots
+  say <text>                      banner for every player";
+
+/// Banned addresses and player names, kept until the server stops
+#[derive(Default)]
+pub struct BanList {
+    ips: Mutex<HashSet<IpAddr>>,
+    names: Mutex<HashSet<String>>, // trimmed, lower case
+}
+
+impl BanList {
+    pub fn is_ip_banned(&self, ip: IpAddr) -> bool {
+        self.ips.lock().unwrap().contains(&ip)
+    }
+
+    pub fn is_name_banned(&self, name: &str) -> bool {
+        self.names.lock().unwrap().contains(&normalize_name(name))
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Server admin commands against the running rooms
+/// Shared by the stdin console, `POST /admin` and `ws://.../admin`; the
+/// remote endpoints need `[admin] token`.
+#[derive(Clone)]
+pub struct Admin {
+    rooms: Arc<RoomManager>,
+    token: Option<String>,
+}
+
+impl Admin {
+    pub fn new(rooms: Arc<RoomManager>, token: Option<String>) -> Self {
+        Self { rooms, token }
+    }
+
+    /// True if remote admin is enabled and `token` is the configured one
+    pub fn authorize(&self, token: Option<&str>) -> bool {
+        match (&self.token, token) {
+            (Some(expected), Some(given)) => constant_time_eq(expected.as_bytes(), given.as_bytes()),
+            _ => false,
+        }
+    }
+
+    /// Run one command line and return the reply
+    pub async fn execute(&self, line: &str) -> String {
+        let line = line.trim();
+        if line.is_empty() {
+            return String::new();
+        }
+        info!(command = line, "Admin command");
+        match self.run(line).await {
+            Ok(reply) => reply,
+            Err(e) => format!("error: {}", e),
+        }
+    }
+
+    async fn run(&self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["help"] => Ok(HELP.to_string()),
+            ["rooms"] => Ok(self.list_rooms().await),
+            ["players"] => Ok(self.list_players(self.rooms.rooms().await).await),
+            ["players", room] => Ok(self.list_players(vec![self.room(room).await?]).await),
+            ["kick", id] => self.kick(parse_player_id(id)?, KICK_REASON).await,
+            ["kick", id, ..] => self.kick(parse_player_id(id)?, skip_words(line, 2)).await,
+            ["ban", "ip", ip] => self.ban_ip(parse_ip(ip)?).await,
+            ["ban", "name", _, ..] => self.ban_name(skip_words(line, 2)).await,
+            ["unban", "ip", ip] => {
+                let ip = parse_ip(ip)?;
+                match self.rooms.bans.ips.lock().unwrap().remove(&ip) {
+                    true => Ok(format!("unbanned {}", ip)),
+                    false => Err(format!("{} is not banned", ip)),
+                }
+            }
+            ["unban", "name", _, ..] => {
+                let name = normalize_name(skip_words(line, 2));
+                match self.rooms.bans.names.lock().unwrap().remove(&name) {
+                    true => Ok(format!("unbanned name `{}`", name)),
+                    false => Err(format!("name `{}` is not banned", name)),
+                }
+            }
+            ["bans"] => Ok(self.list_bans()),
+            ["status", room, status] => self.force_status(&self.room(room).await?, status).await,
+            ["reset", room] => {
+                let room = self.room(room).await?;
+                room.game_state.lock().await.reset_round();
+                Ok(format!("reset `{}`", room.name))
+            }
+            ["set", room, key, _, ..] => self.set(&self.room(room).await?, key, skip_words(line, 3)).await,
+            ["dots", room, count] => self.spawn_dots(&self.room(room).await?, count).await,
+            ["say", _, ..] => Ok(self.announce(skip_words(line, 1)).await),
+            _ => Err(format!("unknown command or wrong arguments: `{}` (try `help`)", line)),
+        }
+    }
+
+    /// Room by id or name
+    async fn room(&self, key: &str) -> Result<Arc<Room>, String> {
+        let id = key.parse::<u64>().ok();
+        self.rooms
+            .rooms()
+            .await
+            .into_iter()
+            .find(|room| Some(room.id) == id || room.name == key)
+            .ok_or_else(|| format!("no room `{}`", key))
+    }
+
+    async fn list_rooms(&self) -> String {
+        let mut lines = Vec::new();
+        for room in self.rooms.rooms().await {
+            let gs = room.game_state.lock().await;
+            lines.push(format!(
+                "{} `{}`: {:?}, {}/{} players, {} bots, tick {}",
+                room.id,
+                room.name,
+                gs.status,
+                gs.player_count(),
+                room.max_players,
+                gs.bot_count(),
+                gs.tick
+            ));
+        }
+        lines.join("\n")
+    }
+
+    async fn list_players(&self, rooms: Vec<Arc<Room>>) -> String {
+        let mut lines = Vec::new();
+        for room in rooms {
+            let gs = room.game_state.lock().await;
+            let conns = room.connections.lock().await;
+            lines.push(format!("room {} `{}`:", room.id, room.name));
+            let mut players: Vec<_> = gs.players.values().collect();
+            players.sort_by_key(|p| p.id);
+            for p in players {
+                let address = match conns.get(&p.id) {
+                    Some(conn) => conn.ip.to_string(),
+                    None if p.bot => "bot".to_string(),
+                    None => "-".to_string(),
+                };
+                lines.push(format!("  {} {:?} score {} ({})", p.id, p.name, p.score, address));
+            }
+            let parked = gs.parked_count();
+            if parked > 0 {
+                lines.push(format!("  + {} disconnected, waiting for a resume", parked));
+            }
+        }
+        lines.join("\n")
+    }
+
+    async fn kick(&self, id: u64, reason: &str) -> Result<String, String> {
+        for room in self.rooms.rooms().await {
+            if room.game_state.lock().await.players.get(&id).is_some_and(|p| p.bot) {
+                return Err(format!("player {} is a bot", id));
+            }
+            if room.kick(id, reason).await {
+                self.rooms.player_left().await;
+                return Ok(format!("kicked player {} from `{}`", id, room.name));
+            }
+        }
+        Err(format!("no player {}", id))
+    }
+
+    async fn ban_ip(&self, ip: IpAddr) -> Result<String, String> {
+        self.rooms.bans.ips.lock().unwrap().insert(ip);
+        let mut kicked = 0;
+        for room in self.rooms.rooms().await {
+            let ids: Vec<u64> = {
+                let conns = room.connections.lock().await;
+                conns.iter().filter(|(_, conn)| conn.ip == ip).map(|(id, _)| *id).collect()
+            };
+            for id in ids {
+                kicked += room.kick(id, BAN_REASON).await as usize;
+            }
+        }
+        self.rooms.player_left().await;
+        Ok(format!("banned {}, {} players dropped", ip, kicked))
+    }
+
+    async fn ban_name(&self, name: &str) -> Result<String, String> {
+        let name = normalize_name(name);
+        self.rooms.bans.names.lock().unwrap().insert(name.clone());
+        let mut kicked = 0;
+        for room in self.rooms.rooms().await {
+            // Parked players too, or they could resume under the banned name
+            let ids: Vec<u64> = {
+                let gs = room.game_state.lock().await;
+                gs.humans().filter(|p| normalize_name(&p.name) == name).map(|p| p.id).collect()
+            };
+            for id in ids {
+                kicked += room.kick(id, BAN_REASON).await as usize;
+            }
+        }
+        self.rooms.player_left().await;
+        Ok(format!("banned name `{}`, {} players dropped", name, kicked))
+    }
+
+    fn list_bans(&self) -> String {
+        let bans = &self.rooms.bans;
+        let mut ips: Vec<String> = bans.ips.lock().unwrap().iter().map(|ip| ip.to_string()).collect();
+        let mut names: Vec<String> = bans.names.lock().unwrap().iter().cloned().collect();
+        ips.sort();
+        names.sort();
+        let list = |items: Vec<String>| if items.is_empty() { "(none)".to_string() } else { items.join(", ") };
+        format!("addresses: {}\nnames: {}", list(ips), list(names))
+    }
+
+    async fn force_status(&self, room: &Arc<Room>, status: &str) -> Result<String, String> {
+        let status = match status {
+            "lobby" | "waiting" => GameStatus::WaitingToStart,
+            "playing" => GameStatus::Playing,
+            "gameover" | "results" => GameStatus::GameOver,
+            _ => return Err(format!("unknown status `{}` (lobby, playing or gameover)", status)),
+        };
+        room.game_state.lock().await.force_status(status);
+        Ok(format!("`{}` is now {:?}", room.name, status))
+    }
+
+    async fn set(&self, room: &Arc<Room>, key: &str, value: &str) -> Result<String, String> {
+        if RESTART_SETTINGS.contains(&key) {
+            return Err(format!("`{}` cannot change while the room runs, edit the config and restart", key));
+        }
+        let mut gs = room.game_state.lock().await;
+        let config = gs.config.with_value(key, value).map_err(|e| e.to_string())?;
+        if config.lobby_min_players > room.max_players {
+            return Err(format!(
+                "lobby_min_players ({}) cannot exceed the room's max_players ({})",
+                config.lobby_min_players, room.max_players
+            ));
+        }
+        gs.apply_config(config);
+        Ok(format!("`{}`: {} = {}", room.name, key, value))
+    }
+
+    async fn spawn_dots(&self, room: &Arc<Room>, count: &str) -> Result<String, String> {
+        let count: usize = count.parse().map_err(|_| format!("`{}` is not a number", count))?;
+        if count > MAX_SPAWN_DOTS {
+            return Err(format!("at most {} dots at once", MAX_SPAWN_DOTS));
+        }
+        let mut gs = room.game_state.lock().await;
+        let spawned = (0..count).filter(|_| gs.spawn_new_dot()).count();
+        Ok(format!("spawned {} dots in `{}` ({} now)", spawned, room.name, gs.dots.len()))
+    }
+
+    async fn announce(&self, text: &str) -> String {
+        let msg = ServerMessage::Announcement(AnnouncementMessage {
+            text: text.to_string(),
+            duration_ms: ANNOUNCEMENT_MS,
+        });
+        let mut sent = 0;
+        for room in self.rooms.rooms().await {
+            for conn in room.connections.lock().await.values() {
+                sent += conn.send(&msg) as usize;
+            }
+        }
+        format!("announced to {} players", sent)
+    }
+}
+
+/// Token of an `Authorization: Bearer <token>` header value
+pub fn bearer_token(header: &str) -> Option<&str> {
+    header.strip_prefix("Bearer ").map(str::trim)
+}
+
+/// Read admin commands from stdin until it closes
+pub async fn run_console(admin: Admin) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let reply = admin.execute(&line).await;
+        if !reply.is_empty() {
+            println!("{}", reply);
+        }
+    }
+}
+
+/// Answer admin commands sent as text messages on an authorized websocket
+pub async fn serve_websocket(mut ws: WebSocketStream<TcpStream>, admin: Admin) {
+    while let Some(Ok(msg)) = ws.next().await {
+        match msg {
+            Message::Text(line) => {
+                let reply = admin.execute(&line).await;
+                if ws.send(Message::Text(reply)).await.is_err() {
+                    break;
+                }
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+}
+
+/// Text after the first `n` words of `line`
+fn skip_words(line: &str, n: usize) -> &str {
+    let mut rest = line.trim();
+    for _ in 0..n {
+        rest = rest.split_once(char::is_whitespace).map_or("", |(_, tail)| tail.trim_start());
+    }
+    rest
+}
+
+fn parse_player_id(text: &str) -> Result<u64, String> {
+    text.parse().map_err(|_| format!("`{}` is not a player id", text))
+}
+
+fn parse_ip(text: &str) -> Result<IpAddr, String> {
+    text.parse().map_err(|_| format!("`{}` is not an IP address", text))
+}
+
+/// Compare secrets without an early exit on the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::protocol::ClientMessage;
+    use crate::config::ServerConfig;
+
+    async fn admin() -> (Admin, Arc<Room>) {
+        let rooms = Arc::new(RoomManager::new(&ServerConfig::default(), None).await);
+        let room = rooms.rooms().await.remove(0);
+        (Admin::new(rooms, None), room)
+    }
+
+    #[tokio::test]
+    async fn name_bans_drop_connected_and_parked_players() {
+        let (admin, room) = admin().await;
+        {
+            let mut gs = room.game_state.lock().await;
+            for (id, name) in [(1, "Bob"), (2, " bob "), (3, "Alice")] {
+                gs.add_player(id);
+                gs.handle_message(id, ClientMessage::Join { name: name.to_string() });
+            }
+            gs.park_player(2, 100);
+        }
+        assert_eq!(admin.execute("ban name BOB").await, "banned name `bob`, 2 players dropped");
+
+        let mut gs = room.game_state.lock().await;
+        assert!(!gs.has_player(1));
+        assert!(!gs.has_player(2));
+        // Nobody can take the name afterwards
+        gs.handle_message(3, ClientMessage::Join { name: "Bob".to_string() });
+        assert_eq!(gs.players[&3].name, "Alice");
+    }
+}
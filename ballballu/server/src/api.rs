@@ -0,0 +1,242 @@
+use std::sync::Arc;
+use std::time::Instant;
+use hyper::body::Bytes;
+use hyper::{Method, Response, StatusCode};
+use serde::Serialize;
+
+use shared::objects::LeaderboardEntry;
+use shared::{GameConstant, GameStatus, RoundState};
+use crate::hall_of_fame::HallOfFame;
+use crate::room_manager::{Room, RoomManager};
+
+type ApiResponse = Response<http_body_util::Full<Bytes>>;
+
+/// What the read-only JSON API can see
+#[derive(Clone)]
+pub struct ApiState {
+    pub rooms: Arc<RoomManager>,
+    pub hall_of_fame: Option<HallOfFame>,
+    pub started_at: Instant, // for the uptime in /api/status
+}
+
+#[derive(Serialize)]
+struct Status {
+    uptime_secs: u64,
+    players: usize, // humans in all rooms
+    rooms: Vec<RoomStatus>,
+}
+
+#[derive(Serialize)]
+struct RoomStatus {
+    id: u64,
+    name: String,
+    tick: u64,
+    status: GameStatus,
+    players: usize, // humans, including disconnected ones that may resume
+    bots: usize,
+    max_players: usize,
+    round: RoundState,
+}
+
+#[derive(Serialize)]
+struct PlayerInfo {
+    id: u64,
+    name: String,
+    score: u32,
+    x: f32,
+    y: f32,
+    radius: f32,
+    cells: usize,
+    team: Option<u8>,
+    bot: bool,
+}
+
+#[derive(Serialize)]
+struct RoomPlayers {
+    room: String,
+    players: Vec<PlayerInfo>,
+}
+
+#[derive(Serialize)]
+struct RoomLeaderboard {
+    room: String,
+    leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize)]
+struct RoomConstants {
+    room: String,
+    constants: GameConstant,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: String,
+}
+
+/// Answer a request below `/api/`
+/// Per-room endpoints list every room, or only the one named by `?room=`.
+///    - /api/status:       uptime, player counts, tick and status of each room
+///    - /api/players:      names, scores and positions
+///    - /api/leaderboard:  top players of each room
+///    - /api/constants:    game constants of each room
+///    - /api/hall-of-fame: all-time and daily best lives
+pub async fn handle(method: &Method, path: &str, query: Option<&str>, state: &ApiState) -> ApiResponse {
+    if method != Method::GET {
+        return error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported".to_string());
+    }
+
+    if path == "/api/hall-of-fame" {
+        return match &state.hall_of_fame {
+            Some(hall_of_fame) => json(StatusCode::OK, &hall_of_fame.top().await),
+            None => error(StatusCode::NOT_FOUND, "hall of fame is disabled".to_string()),
+        };
+    }
+
+    let rooms = match select_rooms(&state.rooms, query).await {
+        Ok(rooms) => rooms,
+        Err(response) => return response,
+    };
+    match path {
+        "/api/status" => {
+            let mut list = Vec::with_capacity(rooms.len());
+            for room in &rooms {
+                let gs = room.game_state.lock().await;
+                list.push(RoomStatus {
+                    id: room.id,
+                    name: room.name.clone(),
+                    tick: gs.tick,
+                    status: gs.status,
+                    players: gs.player_count(),
+                    bots: gs.bot_count(),
+                    max_players: room.max_players,
+                    round: gs.round_state().clone(),
+                });
+            }
+            let status = Status {
+                uptime_secs: state.started_at.elapsed().as_secs(),
+                players: list.iter().map(|r| r.players).sum(),
+                rooms: list,
+            };
+            json(StatusCode::OK, &status)
+        }
+        "/api/players" => {
+            let mut list = Vec::with_capacity(rooms.len());
+            for room in &rooms {
+                let gs = room.game_state.lock().await;
+                let mut players: Vec<PlayerInfo> = gs
+                    .players
+                    .values()
+                    .map(|p| PlayerInfo {
+                        id: p.id,
+                        name: p.name.clone(),
+                        score: p.score,
+                        x: p.x,
+                        y: p.y,
+                        radius: p.radius,
+                        cells: p.cells.len(),
+                        team: p.team,
+                        bot: p.bot,
+                    })
+                    .collect();
+                players.sort_by_key(|p| p.id);
+                list.push(RoomPlayers { room: room.name.clone(), players });
+            }
+            json(StatusCode::OK, &list)
+        }
+        "/api/leaderboard" => {
+            let mut list = Vec::with_capacity(rooms.len());
+            for room in &rooms {
+                let gs = room.game_state.lock().await;
+                let leaderboard = gs.leaderboard(gs.config.leaderboard_size);
+                list.push(RoomLeaderboard { room: room.name.clone(), leaderboard });
+            }
+            json(StatusCode::OK, &list)
+        }
+        "/api/constants" => {
+            let mut list = Vec::with_capacity(rooms.len());
+            for room in &rooms {
+                let constants = room.game_state.lock().await.constants.clone();
+                list.push(RoomConstants { room: room.name.clone(), constants });
+            }
+            json(StatusCode::OK, &list)
+        }
+        _ => error(StatusCode::NOT_FOUND, format!("no such endpoint `{}`", path)),
+    }
+}
+
+/// Every room, or the one named by `?room=` (404 if there is none)
+async fn select_rooms(rooms: &RoomManager, query: Option<&str>) -> Result<Vec<Arc<Room>>, ApiResponse> {
+    let all = rooms.rooms().await;
+    let Some(name) = query.and_then(|q| query_param(q, "room")) else {
+        return Ok(all);
+    };
+    let selected: Vec<Arc<Room>> = all.into_iter().filter(|room| room.name == name).collect();
+    if selected.is_empty() {
+        return Err(error(StatusCode::NOT_FOUND, format!("no room named `{}`", name)));
+    }
+    Ok(selected)
+}
+
+/// Value of `key` in a query string, with `+` and `%XX` decoded
+pub(crate) fn query_param(query: &str, key: &str) -> Option<String> {
+    let raw = query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        (k == key).then_some(v)
+    })?;
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match raw.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn json<T: Serialize>(status: StatusCode, body: &T) -> ApiResponse {
+    let body = serde_json::to_string(body).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(http_body_util::Full::new(Bytes::from(body)))
+        .unwrap()
+}
+
+fn error(status: StatusCode, message: String) -> ApiResponse {
+    json(status, &ApiError { error: message })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_params_are_percent_decoded() {
+        assert_eq!(query_param("room=big+room&x=1", "room").as_deref(), Some("big room"));
+        assert_eq!(query_param("name=Zo%C3%AB%21", "name").as_deref(), Some("Zoë!"));
+        assert_eq!(query_param("a=1&b=2&a=3", "a").as_deref(), Some("1"));
+        assert_eq!(query_param("flag&b=2", "flag").as_deref(), Some(""));
+        assert_eq!(query_param("a=1", "b"), None);
+    }
+
+    #[test]
+    fn broken_escapes_are_kept_as_they_are() {
+        assert_eq!(query_param("q=100%", "q").as_deref(), Some("100%"));
+        assert_eq!(query_param("q=%zz%4", "q").as_deref(), Some("%zz%4"));
+        assert_eq!(query_param("q=%é", "q").as_deref(), Some("%é"));
+        assert_eq!(query_param("q=%ff", "q").as_deref(), Some("\u{fffd}"));
+    }
+}
@@ -0,0 +1,68 @@
+use rand::Rng;
+
+use crate::game_state::GameState;
+
+/// Bot ids start here so they never clash with connection ids
+pub const BOT_ID_BASE: u64 = 1 << 48;
+
+// How far a bot looks for threats, prey and food (world units)
+const SIGHT: f32 = 400.0;
+// Each move lasts this long before the bot decides again
+const PLAN_MS: f32 = 300.0;
+
+/// Next move of a bot as (dx, dy, distance), for `GameState::queue_move`
+/// Priorities: run from the nearest player that can eat it, chase the
+/// nearest player it can eat, go for the nearest dot, else wander.
+/// None while the previous move is still running.
+pub fn decide(gs: &GameState, id: u64) -> Option<(f32, f32, f32)> {
+    let me = gs.players.get(&id)?;
+    if me.remaining_distance > 0.0 {
+        return None;
+    }
+    let distance = me.speed * PLAN_MS / 1000.0;
+    let size_threshold = gs.constants.collide_size_fraction;
+    let dist2 = |x: f32, y: f32| (x - me.x).powi(2) + (y - me.y).powi(2);
+
+    let mut threat: Option<(f32, f32, f32)> = None; // (distance², x, y)
+    let mut prey: Option<(f32, f32, f32)> = None;
+    for other in gs.players.values() {
+        if other.id == id || gs.same_team(id, other.id) {
+            continue;
+        }
+        let d2 = dist2(other.x, other.y);
+        let reach = SIGHT + other.radius;
+        if d2 > reach * reach {
+            continue;
+        }
+        let slot = if other.radius > me.radius * size_threshold {
+            &mut threat
+        } else if me.radius > other.radius * size_threshold {
+            &mut prey
+        } else {
+            continue;
+        };
+        if slot.is_none_or(|(best, _, _)| d2 < best) {
+            *slot = Some((d2, other.x, other.y));
+        }
+    }
+
+    if let Some((_, x, y)) = threat {
+        return Some((me.x - x, me.y - y, distance));
+    }
+    if let Some((_, x, y)) = prey {
+        return Some((x - me.x, y - me.y, distance));
+    }
+
+    let food = gs
+        .dot_grid
+        .query(me.x, me.y, SIGHT)
+        .into_iter()
+        .filter_map(|did| gs.dots.get(&did))
+        .min_by(|a, b| dist2(a.x, a.y).total_cmp(&dist2(b.x, b.y)));
+    if let Some(dot) = food {
+        return Some((dot.x - me.x, dot.y - me.y, distance));
+    }
+
+    let angle = rand::thread_rng().gen_range(0.0..std::f32::consts::TAU);
+    Some((angle.cos(), angle.sin(), distance))
+}
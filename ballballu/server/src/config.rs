@@ -0,0 +1,734 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use clap::Parser;
+use serde::{Serialize, Deserialize};
+use tracing_subscriber::EnvFilter;
+
+use shared::{GameConstant, MovementMode, MAX_TEAMS};
+
+/// Command line arguments
+/// Every flag overrides the matching value from the config file
+#[derive(Debug, Parser)]
+#[command(name = "server", about = "Ball Ball U game server")]
+pub struct Cli {
+    /// Path to a TOML config file
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// WebSocket bind address (e.g. 0.0.0.0:34568)
+    #[arg(long)]
+    pub ws_addr: Option<String>,
+
+    /// HTTP bind address (e.g. 0.0.0.0:34567)
+    #[arg(long)]
+    pub http_addr: Option<String>,
+
+    /// Milliseconds between game ticks
+    #[arg(long)]
+    pub tick_ms: Option<u64>,
+
+    /// World width in world units
+    #[arg(long)]
+    pub world_width: Option<f32>,
+
+    /// World height in world units
+    #[arg(long)]
+    pub world_height: Option<f32>,
+
+    /// Number of dots spawned when the world is created
+    #[arg(long)]
+    pub dot_count: Option<usize>,
+
+    /// Radius of a player with score 0
+    #[arg(long)]
+    pub base_radius: Option<f32>,
+
+    /// Log filter, e.g. `info` or `server=debug,server::game_loop=trace`
+    /// (`RUST_LOG` overrides both this and the config file)
+    #[arg(long)]
+    pub log: Option<String>,
+}
+
+/// Network settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub ws_addr: String,   // WebSocket game server
+    pub http_addr: String, // static file server
+    pub delta_updates: bool,       // send deltas to clients that acknowledge snapshots
+    pub keyframe_interval: u64,    // ticks between forced full snapshots
+    pub resume_grace_ms: u64,      // disconnected players wait this long for a Resume (0 = removed at once)
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            ws_addr: "0.0.0.0:34568".to_string(),
+            http_addr: "0.0.0.0:34567".to_string(),
+            delta_updates: true,
+            keyframe_interval: 40,
+            resume_grace_ms: 30_000,
+        }
+    }
+}
+
+/// One entry of the dot type table
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DotType {
+    pub score: u32,
+    pub color: (u8, u8, u8),
+    pub radius: f32,
+}
+
+/// Gameplay settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub tick_interval_ms: u64,
+    pub collide_size_fraction: f32,
+    pub move_speed_base: f32,
+    pub dot_radius: f32,
+    pub base_radius: f32,        // player radius at score 0
+    pub world_width: f32,
+    pub world_height: f32,
+    pub initial_dot_count: usize,
+    pub dot_types: Vec<DotType>, // dot is picked uniformly from this table
+    pub leaderboard_size: usize, // top N players sent with every snapshot
+    pub movement_mode: MovementMode, // "discrete" (arrow key steps) or "continuous" (mouse)
+    pub min_split_score: u32,    // cells below this score do not split
+    pub max_cells: usize,        // cells per player
+    pub split_speed: f32,        // launch speed of a split half (units/sec)
+    pub merge_cooldown_ms: u64,  // split cells merge back after this long
+    pub eject_score: u32,        // score moved from a cell into one ejected pellet
+    pub eject_min_score: u32,    // cells below this score do not eject
+    pub eject_speed: f32,        // launch speed of a pellet (units/sec)
+    pub eject_radius: f32,
+    pub eject_color: (u8, u8, u8),
+    pub virus_count: usize,      // viruses kept in the world
+    pub virus_max: usize,        // cap including viruses fired by feeding
+    pub virus_radius: f32,
+    pub virus_score: u32,        // score gained by a cell that pops on a virus
+    pub virus_pieces: usize,     // a popped cell bursts into this many cells
+    pub virus_feed_count: u32,   // pellets needed to make a virus fire a new one
+    pub virus_fire_speed: f32,   // launch speed of a fired virus (units/sec)
+    pub decay_threshold: u32,    // cells keep this much score untouched
+    pub decay_percent_per_sec: f32, // share of the score above the threshold lost per second (0 = off)
+    pub lobby_min_players: usize, // players needed (all ready) before the countdown starts
+    pub lobby_countdown_ms: u64,  // countdown before the game starts (0 = start at once)
+    pub round_duration_ms: u64,   // length of a round (0 = no time limit)
+    pub results_duration_ms: u64, // results are shown this long before the next round
+    pub team_count: usize,        // 0 = free-for-all, otherwise 2..=MAX_TEAMS teams
+    pub bot_count: usize,         // bots fill the room up to this many players (0 = no bots)
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval_ms: 50,
+            collide_size_fraction: 1.001,
+            move_speed_base: 150.0,
+            dot_radius: 5.0,
+            base_radius: 10.0,
+            world_width: 2000.0,
+            world_height: 2000.0,
+            initial_dot_count: 150,
+            dot_types: vec![
+                DotType { score: 2, color: (100, 150, 255), radius: 4.0 },  // Blue, small
+                DotType { score: 5, color: (255, 255, 100), radius: 6.0 },  // Yellow, medium
+                DotType { score: 10, color: (255, 100, 100), radius: 8.0 }, // Red, large
+            ],
+            leaderboard_size: 10,
+            movement_mode: MovementMode::Discrete,
+            min_split_score: 30,
+            max_cells: 16,
+            split_speed: 600.0,
+            merge_cooldown_ms: 10_000,
+            eject_score: 12,
+            eject_min_score: 30,
+            eject_speed: 500.0,
+            eject_radius: 7.0,
+            eject_color: (200, 200, 200),
+            virus_count: 10,
+            virus_max: 20,
+            virus_radius: 30.0,
+            virus_score: 50,
+            virus_pieces: 8,
+            virus_feed_count: 7,
+            virus_fire_speed: 600.0,
+            decay_threshold: 200,
+            decay_percent_per_sec: 0.5,
+            lobby_min_players: 1,
+            lobby_countdown_ms: 5_000,
+            round_duration_ms: 300_000,
+            results_duration_ms: 10_000,
+            team_count: 0,
+            bot_count: 4,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Check the gameplay values; `section` names the table in error messages
+    /// (`game`, or a room preset)
+    pub fn validate(&self, section: &str) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+
+        if self.tick_interval_ms == 0 {
+            return invalid(format!("{}.tick_interval_ms must be greater than 0", section));
+        }
+        if self.collide_size_fraction.is_nan() || self.collide_size_fraction < 1.0 {
+            return invalid(format!(
+                "{}.collide_size_fraction must be at least 1.0, got {}",
+                section, self.collide_size_fraction
+            ));
+        }
+        if !is_positive(self.move_speed_base) {
+            return invalid(format!("{}.move_speed_base must be positive, got {}", section, self.move_speed_base));
+        }
+        if !is_positive(self.dot_radius) {
+            return invalid(format!("{}.dot_radius must be positive, got {}", section, self.dot_radius));
+        }
+        if !is_positive(self.base_radius) {
+            return invalid(format!("{}.base_radius must be positive, got {}", section, self.base_radius));
+        }
+        if !(self.world_width.is_finite() && self.world_height.is_finite()) {
+            return invalid(format!("{0}.world_width and {0}.world_height must be finite", section));
+        }
+
+        // Every spawned entity must fit inside the world
+        let max_dot_radius = self.dot_types.iter().map(|d| d.radius).fold(0.0, f32::max);
+        let min_extent = 2.0 * self.base_radius.max(max_dot_radius);
+        if self.world_width <= min_extent || self.world_height <= min_extent {
+            return invalid(format!(
+                "{}: world {}x{} is too small, both sides must exceed {}",
+                section, self.world_width, self.world_height, min_extent
+            ));
+        }
+
+        if self.dot_types.is_empty() {
+            return invalid(format!("{}.dot_types must contain at least one entry", section));
+        }
+        for (i, dot) in self.dot_types.iter().enumerate() {
+            if dot.score == 0 {
+                return invalid(format!("{}.dot_types[{}].score must be greater than 0", section, i));
+            }
+            if !is_positive(dot.radius) {
+                return invalid(format!("{}.dot_types[{}].radius must be positive, got {}", section, i, dot.radius));
+            }
+        }
+
+        if self.min_split_score < 2 {
+            return invalid(format!("{}.min_split_score must be at least 2, got {}", section, self.min_split_score));
+        }
+        if self.max_cells == 0 {
+            return invalid(format!("{}.max_cells must be greater than 0", section));
+        }
+        if !(self.split_speed.is_finite() && self.split_speed >= 0.0) {
+            return invalid(format!("{}.split_speed must be 0 or more, got {}", section, self.split_speed));
+        }
+
+        if self.eject_score == 0 {
+            return invalid(format!("{}.eject_score must be greater than 0", section));
+        }
+        if !(self.eject_speed.is_finite() && self.eject_speed >= 0.0) {
+            return invalid(format!("{}.eject_speed must be 0 or more, got {}", section, self.eject_speed));
+        }
+        if !is_positive(self.eject_radius) || 2.0 * self.eject_radius >= self.world_width.min(self.world_height) {
+            return invalid(format!("{}.eject_radius must be positive and fit the world, got {}", section, self.eject_radius));
+        }
+
+        if self.virus_max < self.virus_count {
+            return invalid(format!(
+                "{0}.virus_max ({1}) must be at least {0}.virus_count ({2})",
+                section, self.virus_max, self.virus_count
+            ));
+        }
+        if !is_positive(self.virus_radius) || 2.0 * self.virus_radius >= self.world_width.min(self.world_height) {
+            return invalid(format!("{}.virus_radius must be positive and fit the world, got {}", section, self.virus_radius));
+        }
+        if self.virus_pieces < 2 {
+            return invalid(format!("{}.virus_pieces must be at least 2, got {}", section, self.virus_pieces));
+        }
+        if self.virus_feed_count == 0 {
+            return invalid(format!("{}.virus_feed_count must be greater than 0", section));
+        }
+        if !(self.virus_fire_speed.is_finite() && self.virus_fire_speed >= 0.0) {
+            return invalid(format!("{}.virus_fire_speed must be 0 or more, got {}", section, self.virus_fire_speed));
+        }
+
+        if !(self.decay_percent_per_sec.is_finite() && (0.0..=100.0).contains(&self.decay_percent_per_sec)) {
+            return invalid(format!(
+                "{}.decay_percent_per_sec must be between 0 and 100, got {}",
+                section, self.decay_percent_per_sec
+            ));
+        }
+
+        if self.lobby_min_players == 0 {
+            return invalid(format!("{}.lobby_min_players must be greater than 0", section));
+        }
+        if self.results_duration_ms == 0 {
+            return invalid(format!("{}.results_duration_ms must be greater than 0", section));
+        }
+        if self.team_count == 1 || self.team_count > MAX_TEAMS {
+            return invalid(format!(
+                "{}.team_count must be 0 (free-for-all) or between 2 and {}, got {}",
+                section, MAX_TEAMS, self.team_count
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Constants shared with clients
+    pub fn to_constants(&self) -> GameConstant {
+        GameConstant {
+            tick_interval_ms: self.tick_interval_ms,
+            collide_size_fraction: self.collide_size_fraction,
+            move_speed_base: self.move_speed_base,
+            dot_radius: self.dot_radius,
+            world_width: self.world_width,
+            world_height: self.world_height,
+            movement_mode: self.movement_mode,
+            team_count: self.team_count,
+        }
+    }
+
+    /// Copy with one setting changed, e.g. `("move_speed_base", "200")`
+    /// The value is parsed as a TOML value (a bare word counts as a string)
+    /// and the result is validated like a config file.
+    pub fn with_value(&self, key: &str, value: &str) -> Result<GameConfig, ConfigError> {
+        let invalid = |msg: String| ConfigError::Invalid(msg);
+
+        let mut table = toml::Table::try_from(self).map_err(|e| invalid(e.to_string()))?;
+        let Some(current) = table.get(key) else {
+            return Err(invalid(format!("unknown game setting `{}`", key)));
+        };
+        let parsed = toml::from_str::<toml::Table>(&format!("value = {}", value))
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+        let parsed = match (current, parsed) {
+            (toml::Value::Float(_), toml::Value::Integer(i)) => toml::Value::Float(i as f64),
+            (_, parsed) => parsed,
+        };
+        table.insert(key.to_string(), parsed);
+
+        let config: GameConfig = table
+            .try_into()
+            .map_err(|e: toml::de::Error| invalid(format!("{}: {}", key, e.message())))?;
+        config.validate("game")?;
+        Ok(config)
+    }
+}
+
+/// Area-of-interest filtering for broadcasts
+/// Each client only receives entities within
+/// `view_radius + size_scale * own radius` of its player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterestConfig {
+    pub enabled: bool,
+    pub view_radius: f32, // must cover the client viewport (1000x750 => ~625)
+    pub size_scale: f32,  // bigger players see further
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            view_radius: 800.0,
+            size_scale: 2.0,
+        }
+    }
+}
+
+/// Game rooms hosted by this server
+/// Every room is an independent world. Players are put into the first room
+/// with a free slot; when all are full a new room is opened (and closed again
+/// once it is empty). Clients can also ask for a room by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoomsConfig {
+    pub max_players: usize,       // per room, unless the preset sets its own
+    pub max_rooms: usize,         // presets plus rooms opened on demand
+    pub presets: Vec<RoomPreset>, // rooms open from startup; none = a single room "main"
+}
+
+impl Default for RoomsConfig {
+    fn default() -> Self {
+        Self {
+            max_players: 20,
+            max_rooms: 16,
+            presets: Vec::new(),
+        }
+    }
+}
+
+/// A room that exists from startup, with its own gameplay settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoomPreset {
+    pub name: String,
+    #[serde(default)]
+    pub max_players: Option<usize>,
+    #[serde(default)]
+    pub game: toml::Table, // overrides on top of [game], same keys
+}
+
+/// All-time leaderboard of single lives, stored in SQLite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HallOfFameConfig {
+    pub enabled: bool,
+    pub db_path: PathBuf, // created if missing
+    pub top_n: usize,     // entries per list (all-time / daily)
+}
+
+impl Default for HallOfFameConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            db_path: PathBuf::from("hall_of_fame.db"),
+            top_n: 10,
+        }
+    }
+}
+
+/// Log output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub filter: String,             // `tracing` env-filter directives, `RUST_LOG` wins over it
+    pub json_file: Option<PathBuf>, // also write JSON lines here (appended, never rotated)
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            json_file: None,
+        }
+    }
+}
+
+/// Admin control channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    pub console: bool,         // read admin commands from stdin
+    pub token: Option<String>, // enables POST /admin and ws://.../admin with this secret
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            console: true,
+            token: None,
+        }
+    }
+}
+
+/// Full server configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub network: NetworkConfig,
+    pub game: GameConfig,
+    pub interest: InterestConfig,
+    pub rooms: RoomsConfig,
+    pub hall_of_fame: HallOfFameConfig,
+    pub logging: LoggingConfig,
+    pub admin: AdminConfig,
+}
+
+/// Errors raised while loading the configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: PathBuf, source: std::io::Error },
+    Parse { path: PathBuf, source: toml::de::Error },
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "cannot read config file {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "cannot parse config file {}: {}", path.display(), source)
+            }
+            ConfigError::Invalid(msg) => write!(f, "invalid configuration: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ServerConfig {
+    /// Load config file (if any), apply CLI overrides and validate
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(addr) = &cli.ws_addr {
+            self.network.ws_addr = addr.clone();
+        }
+        if let Some(addr) = &cli.http_addr {
+            self.network.http_addr = addr.clone();
+        }
+        if let Some(tick_ms) = cli.tick_ms {
+            self.game.tick_interval_ms = tick_ms;
+        }
+        if let Some(width) = cli.world_width {
+            self.game.world_width = width;
+        }
+        if let Some(height) = cli.world_height {
+            self.game.world_height = height;
+        }
+        if let Some(count) = cli.dot_count {
+            self.game.initial_dot_count = count;
+        }
+        if let Some(radius) = cli.base_radius {
+            self.game.base_radius = radius;
+        }
+        if let Some(filter) = &cli.log {
+            self.logging.filter = filter.clone();
+        }
+    }
+
+    /// Check that every value is usable before the server starts
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+        let net = &self.network;
+        let game = &self.game;
+
+        if net.ws_addr.parse::<SocketAddr>().is_err() {
+            return invalid(format!("network.ws_addr `{}` is not a socket address", net.ws_addr));
+        }
+        if net.http_addr.parse::<SocketAddr>().is_err() {
+            return invalid(format!("network.http_addr `{}` is not a socket address", net.http_addr));
+        }
+        if net.ws_addr == net.http_addr {
+            return invalid(format!("network.ws_addr and network.http_addr are both `{}`", net.ws_addr));
+        }
+
+        let hall_of_fame = &self.hall_of_fame;
+        if hall_of_fame.enabled && hall_of_fame.db_path.as_os_str().is_empty() {
+            return invalid("hall_of_fame.db_path must not be empty".to_string());
+        }
+        if hall_of_fame.top_n == 0 {
+            return invalid("hall_of_fame.top_n must be greater than 0".to_string());
+        }
+        if net.keyframe_interval == 0 {
+            return invalid("network.keyframe_interval must be greater than 0".to_string());
+        }
+
+        let logging = &self.logging;
+        if let Err(e) = EnvFilter::try_new(&logging.filter) {
+            return invalid(format!("logging.filter `{}` is not a valid filter: {}", logging.filter, e));
+        }
+        if logging.json_file.as_ref().is_some_and(|path| path.file_name().is_none()) {
+            return invalid("logging.json_file must name a file".to_string());
+        }
+
+        if self.admin.token.as_ref().is_some_and(|token| token.trim().len() < 8) {
+            return invalid("admin.token must be at least 8 characters".to_string());
+        }
+
+        game.validate("game")?;
+
+        let rooms = &self.rooms;
+        if rooms.max_players == 0 {
+            return invalid("rooms.max_players must be greater than 0".to_string());
+        }
+        if game.lobby_min_players > rooms.max_players {
+            return invalid(format!(
+                "game.lobby_min_players ({}) is more than rooms.max_players ({}), rooms could never start",
+                game.lobby_min_players, rooms.max_players
+            ));
+        }
+        if rooms.max_rooms == 0 || rooms.max_rooms < rooms.presets.len() {
+            return invalid(format!(
+                "rooms.max_rooms ({}) must be at least 1 and cover the {} presets",
+                rooms.max_rooms,
+                rooms.presets.len()
+            ));
+        }
+        for (i, preset) in rooms.presets.iter().enumerate() {
+            if preset.name.trim().is_empty() {
+                return invalid(format!("rooms.presets[{}].name must not be empty", i));
+            }
+            if rooms.presets[..i].iter().any(|p| p.name == preset.name) {
+                return invalid(format!("rooms.presets: room `{}` is defined twice", preset.name));
+            }
+            if preset.max_players == Some(0) {
+                return invalid(format!("rooms.presets `{}`: max_players must be greater than 0", preset.name));
+            }
+            let preset_game = self.preset_game_config(preset)?;
+            let max_players = preset.max_players.unwrap_or(rooms.max_players);
+            if preset_game.lobby_min_players > max_players {
+                return invalid(format!(
+                    "rooms.presets `{}`: lobby_min_players ({}) is more than max_players ({})",
+                    preset.name, preset_game.lobby_min_players, max_players
+                ));
+            }
+        }
+
+        let interest = &self.interest;
+        if !is_positive(interest.view_radius) {
+            return invalid(format!("interest.view_radius must be positive, got {}", interest.view_radius));
+        }
+        if !(interest.size_scale.is_finite() && interest.size_scale >= 0.0) {
+            return invalid(format!("interest.size_scale must be 0 or more, got {}", interest.size_scale));
+        }
+
+        Ok(())
+    }
+
+    /// Gameplay settings of a preset room: [game] with the preset's overrides
+    pub fn preset_game_config(&self, preset: &RoomPreset) -> Result<GameConfig, ConfigError> {
+        let section = format!("rooms.presets `{}`.game", preset.name);
+        let mut table = toml::Table::try_from(&self.game)
+            .map_err(|e| ConfigError::Invalid(format!("{}: {}", section, e)))?;
+        table.extend(preset.game.clone());
+        let game: GameConfig = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e| ConfigError::Invalid(format!("{}: {}", section, e)))?;
+        game.validate(&section)?;
+        Ok(game)
+    }
+}
+
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_message<T: fmt::Debug>(result: Result<T, ConfigError>) -> String {
+        match result {
+            Err(ConfigError::Invalid(msg)) => msg,
+            other => panic!("expected ConfigError::Invalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        ServerConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn with_value_changes_one_setting() {
+        let game = GameConfig::default();
+        let changed = game.with_value("move_speed_base", "200").unwrap();
+        assert_eq!(changed.move_speed_base, 200.0);
+        assert_eq!(changed.tick_interval_ms, game.tick_interval_ms);
+
+        let changed = game.with_value("movement_mode", "continuous").unwrap();
+        assert_eq!(changed.movement_mode, MovementMode::Continuous);
+        assert_eq!(game.with_value("team_count", "4").unwrap().team_count, 4);
+    }
+
+    #[test]
+    fn with_value_rejects_unknown_keys_and_bad_values() {
+        let game = GameConfig::default();
+        assert!(invalid_message(game.with_value("warp_speed", "9")).contains("unknown game setting `warp_speed`"));
+        assert!(invalid_message(game.with_value("tick_interval_ms", "fast")).starts_with("tick_interval_ms:"));
+        assert!(invalid_message(game.with_value("movement_mode", "teleport")).starts_with("movement_mode:"));
+        // Parses, but fails validation
+        assert!(invalid_message(game.with_value("tick_interval_ms", "0")).contains("game.tick_interval_ms"));
+        assert!(invalid_message(game.with_value("team_count", "1")).contains("game.team_count"));
+    }
+
+    #[test]
+    fn config_files_reject_unknown_keys() {
+        assert!(toml::from_str::<ServerConfig>("[network]\nws_adr = \"0.0.0.0:9000\"\n").is_err());
+        assert!(toml::from_str::<ServerConfig>("[gaem]\n").is_err());
+        let config: ServerConfig = toml::from_str("[network]\nws_addr = \"0.0.0.0:9000\"\n").unwrap();
+        assert_eq!(config.network.ws_addr, "0.0.0.0:9000");
+    }
+
+    #[test]
+    fn validate_rejects_zero_keyframe_interval() {
+        let mut config = ServerConfig::default();
+        config.network.keyframe_interval = 0;
+        assert!(invalid_message(config.validate()).contains("network.keyframe_interval"));
+    }
+
+    #[test]
+    fn validate_rejects_clashing_addresses() {
+        let mut config = ServerConfig::default();
+        config.network.http_addr = config.network.ws_addr.clone();
+        assert!(invalid_message(config.validate()).contains("are both"));
+    }
+
+    #[test]
+    fn validate_rejects_rooms_that_could_never_open() {
+        let mut config = ServerConfig::default();
+        config.rooms.max_rooms = 0;
+        assert!(invalid_message(config.validate()).contains("rooms.max_rooms"));
+
+        let preset = |name: &str| RoomPreset { name: name.to_string(), max_players: None, game: toml::Table::new() };
+        let mut config = ServerConfig::default();
+        config.rooms.presets = vec![preset("duel"), preset("duel")];
+        assert!(invalid_message(config.validate()).contains("defined twice"));
+        config.rooms.presets = vec![preset("duel"), preset(" ")];
+        assert!(invalid_message(config.validate()).contains("rooms.presets[1].name"));
+    }
+
+    #[test]
+    fn validate_rejects_rooms_that_could_never_start() {
+        let mut config = ServerConfig::default();
+        config.rooms.max_players = 2;
+        config.game.lobby_min_players = 3;
+        assert!(invalid_message(config.validate()).contains("game.lobby_min_players"));
+
+        let mut config = ServerConfig::default();
+        config.rooms.presets = vec![RoomPreset {
+            name: "duel".to_string(),
+            max_players: Some(2),
+            game: toml::from_str("lobby_min_players = 4").unwrap(),
+        }];
+        assert!(invalid_message(config.validate()).contains("rooms.presets `duel`: lobby_min_players"));
+    }
+
+    #[test]
+    fn presets_override_game_settings() {
+        let config: ServerConfig = toml::from_str(
+            "[game]\nmove_speed_base = 120.0\n\n[[rooms.presets]]\nname = \"fast\"\ngame = { move_speed_base = 300.0 }\n",
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let game = config.preset_game_config(&config.rooms.presets[0]).unwrap();
+        assert_eq!(game.move_speed_base, 300.0);
+        assert_eq!(game.tick_interval_ms, config.game.tick_interval_ms);
+
+        let mut broken = config.rooms.presets[0].clone();
+        broken.game.insert("tick_interval_ms".to_string(), toml::Value::Integer(0));
+        assert!(invalid_message(config.preset_game_config(&broken)).contains("rooms.presets `fast`.game.tick_interval_ms"));
+    }
+}
@@ -1,160 +1,373 @@
 use std::sync::Arc;
-use tokio::time::{interval, Duration};
-use crate::websocket_manager::WebSocketManager;
+use tokio::time::{interval, Duration, Instant};
+use tracing::{debug, debug_span, field, info, trace, Instrument, Span};
+use crate::room_manager::Room;
 use crate::game_state::GameState;
+use crate::metrics;
 use shared::GameStatus;
-use shared::mechanics::{update_position, dot_collision_check, cells_collisions_check};
+use shared::mechanics::{
+    update_position, dot_collision_check, cells_collisions_check,
+    consume_calculation, merge_or_separate_cells, refresh_player, virus_pops_cell,
+};
 
+/// Tick task of one room
 pub struct GameLoop {
-    pub ws: Arc<WebSocketManager>,
+    pub room: Arc<Room>,
 }
 
 impl GameLoop {
-    pub fn new(ws: Arc<WebSocketManager>) -> Self {
-        Self { ws }
+    pub fn new(room: Arc<Room>) -> Self {
+        Self { room }
     }
 
     pub async fn run(&self) {
         // Phase 3: Get tick interval from GameState
         let tick_ms = {
-            let gs = self.ws.game_state.lock().await;
+            let gs = self.room.game_state.lock().await;
             gs.constants.tick_interval_ms
         };
 
         let mut ticker = interval(Duration::from_millis(tick_ms));
 
+        // This room's series, looked up once
+        let metrics = metrics::get();
+        let room = self.room.name.as_str();
+        let tick_duration = metrics.tick_duration.with_label_values(&[room]);
+        let tick_overruns = metrics.tick_overruns.with_label_values(&[room]);
+        let bytes_broadcast = metrics.bytes_broadcast.with_label_values(&[room]);
+        let dot_collisions = metrics.collisions.with_label_values(&[room, "dot"]);
+        let cell_collisions = metrics.collisions.with_label_values(&[room, "cell"]);
+        let virus_collisions = metrics.collisions.with_label_values(&[room, "virus"]);
+        let players = metrics.room_players.with_label_values(&[room]);
+        let bots = metrics.room_bots.with_label_values(&[room]);
+        let dots = metrics.room_dots.with_label_values(&[room]);
+
         loop {
             ticker.tick().await;
+            let started = Instant::now();
 
-            {
-                let mut gs = self.ws.game_state.lock().await;
-                
-                // Only process game updates if game is playing
-                if gs.status == GameStatus::Playing {
-                    // Apply pending moves from input commands
-                    gs.apply_pending_moves();
-                    
-                    // Phase 4: Update player positions based on remaining distance
-                    let player_ids: Vec<u64> = gs.players.keys().cloned().collect();
-                    let move_speed_base = gs.constants.move_speed_base;
-                    for id in player_ids {
-                        // Then get mutable player reference
-                        if let Some(player) = gs.players.get_mut(&id) {
-                            // Calculate current speed based on score
-                            let current_speed = shared::mechanics::calculate_speed_from_score(
-                                player.score,
-                                move_speed_base
-                            );
-                            player.speed = current_speed;
-                            
-                            // Update position using shared mechanics (new signature)
-                            update_position(player, current_speed, tick_ms as f32);
-                        }
-                    }
+            async {
+                {
+                    let mut gs = self.room.game_state.lock().await;
+                    Span::current().record("tick", gs.tick);
+                    let stats = run_tick(&mut gs);
+                    dot_collisions.inc_by(stats.dots_eaten);
+                    cell_collisions.inc_by(stats.cells_eaten);
+                    virus_collisions.inc_by(stats.virus_pops);
+                    players.set(gs.player_count() as i64);
+                    bots.set(gs.bot_count() as i64);
+                    dots.set(gs.dots.len() as i64);
+                }
+                self.room.record_finished_lives().await;
 
-                    // Phase 5: Handle player-dot collisions
-                    handle_player_dot_collision(&mut gs);
+                // Phase 3: Broadcast snapshot every tick
+                let bytes = self.room.broadcast_state().await;
+                trace!(bytes, "Broadcast state");
 
-                    // Phase 5: Handle player-player collisions
-                    handle_player_player_collision(&mut gs);
+                let elapsed = started.elapsed();
+                bytes_broadcast.observe(bytes as f64);
+                tick_duration.observe(elapsed.as_secs_f64());
+                if elapsed > Duration::from_millis(tick_ms) {
+                    tick_overruns.inc();
+                    debug!(elapsed_ms = elapsed.as_millis() as u64, "Tick overran its interval");
                 }
-
-                // Phase 3: Increment tick
-                gs.tick += 1;
             }
+            .instrument(debug_span!("tick", tick = field::Empty))
+            .await;
+        }
+    }
+}
+
+/// Collisions handled in one tick
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickStats {
+    pub dots_eaten: u64,  // dots and pellets eaten by cells
+    pub cells_eaten: u64, // cells eaten by other players' cells
+    pub virus_pops: u64,  // cells popped on a virus
+}
+
+/// Advance the world by one tick (movement, collisions, tick counter)
+pub fn run_tick(gs: &mut GameState) -> TickStats {
+    let mut stats = TickStats::default();
+    let tick_ms = gs.constants.tick_interval_ms;
 
-            // Phase 3: Broadcast snapshot every tick
-            println!("[DEBUG] GameLoop: About to broadcast state (tick: {})", {
-                let gs = self.ws.game_state.lock().await;
-                gs.tick
-            });
-            self.ws.broadcast_state().await;
-            //println!("[DEBUG] GameLoop: Broadcast completed");
+    // Disconnected players that did not come back in time
+    gs.expire_parked_players();
+
+    // Lobby countdown, switches to Playing when it runs out
+    gs.update_lobby();
+
+    // Round timer: Playing -> GameOver (results) -> reset and back to the lobby
+    gs.update_round();
+
+    // Bots come and go with the humans and queue their next moves
+    gs.update_bots();
+
+    // Only process game updates if game is playing
+    if gs.status == GameStatus::Playing {
+        // Apply pending moves, splits and ejects from input commands
+        gs.apply_pending_moves();
+        gs.apply_pending_splits();
+        gs.apply_pending_ejects();
+        gs.move_pellets();
+        gs.move_viruses();
+
+        // Large cells lose mass so nobody snowballs forever
+        gs.apply_mass_decay();
+
+        // Phase 4: Update player positions based on remaining distance
+        let player_ids: Vec<u64> = gs.players.keys().cloned().collect();
+        let move_speed_base = gs.constants.move_speed_base;
+        let world_width = gs.constants.world_width;
+        let world_height = gs.constants.world_height;
+        let tick = gs.tick;
+        let base_radius = gs.config.base_radius;
+        for id in player_ids {
+            // Then get mutable player reference
+            if let Some(player) = gs.players.get_mut(&id) {
+                // Calculate current speed based on score (of the largest cell,
+                // so splitting makes a player faster)
+                let largest = player.cells.iter().map(|c| c.score).max().unwrap_or(0);
+                let current_speed = shared::mechanics::calculate_speed_from_score(
+                    largest,
+                    move_speed_base
+                );
+                player.speed = current_speed;
+
+                // Update position using shared mechanics (new signature)
+                update_position(
+                    player,
+                    current_speed,
+                    tick_ms as f32,
+                    world_width,
+                    world_height,
+                );
+                merge_or_separate_cells(player, tick, base_radius);
+            }
         }
+        gs.sync_player_grid();
+
+        // Phase 5: Handle player-dot collisions
+        stats.dots_eaten = handle_player_dot_collision(gs);
+        gs.sync_player_grid();
+
+        // Viruses: fed by pellets, pop big cells
+        handle_virus_feeding(gs);
+        stats.virus_pops = handle_virus_pops(gs);
+        gs.sync_player_grid();
+
+        // Phase 5: Handle player-player collisions
+        stats.cells_eaten = handle_player_player_collision(gs);
     }
+
+    // Phase 3: Increment tick
+    gs.tick += 1;
+    stats
 }
 
-// Phase 5: Player vs Dot collision handler
-fn handle_player_dot_collision(gs: &mut GameState) {
+// Phase 5: Cell vs Dot collision handler, returns the number of dots eaten
+fn handle_player_dot_collision(gs: &mut GameState) -> u64 {
+    let mut count = 0;
     let mut eaten = Vec::new();
     
+    // Only dots in the grid cells around each player cell are candidates
     for (pid, p) in gs.players.iter() {
-        for (did, d) in gs.dots.iter() {
-            if dot_collision_check(p, d) {
-                eaten.push((*pid, *did));
+        for cell in &p.cells {
+            for did in gs.dot_grid.query(cell.x, cell.y, cell.radius) {
+                if let Some(d) = gs.dots.get(&did) {
+                    if dot_collision_check(cell, d) {
+                        eaten.push((*pid, cell.id, did));
+                    }
+                }
             }
         }
     }
 
-    // Apply effects: remove dots and increase player score/radius
-    for (pid, did) in eaten {
+    // Apply effects: remove dots and increase cell score/radius
+    let base_radius = gs.config.base_radius;
+    for (pid, cell_id, did) in eaten {
         // Get dot score before removing
         let dot_score = gs.dots.get(&did).map(|d| d.score).unwrap_or(1);
+        let was_pellet = gs.pellets.contains(&did);
         
-        if gs.dots.remove(&did).is_some() {
+        if gs.remove_dot(did).is_some() {
             if let Some(player) = gs.players.get_mut(&pid) {
-                // Increase score by dot's score value
-                player.score += dot_score;
-                // Recalculate radius based on score
-                player.radius = shared::mechanics::calculate_radius_from_score(
-                    player.score,
-                    10.0 // base radius
-                );
-                println!("Player {} ate Dot {} (score: {})", pid, did, dot_score);
+                if let Some(cell) = player.cells.iter_mut().find(|c| c.id == cell_id) {
+                    // Increase score by dot's score value
+                    cell.score += dot_score;
+                    // Recalculate radius based on score
+                    cell.radius = shared::mechanics::calculate_radius_from_score(
+                        cell.score,
+                        base_radius
+                    );
+                }
+                refresh_player(player, base_radius);
+                trace!(player = pid, dot = did, score = dot_score, "Player ate a dot");
+                gs.record_dot_eaten(pid);
+                count += 1;
             }
             
-            // Spawn a new dot to maintain total count
-            gs.spawn_new_dot();
+            // Spawn a new dot to maintain total count (ejected pellets are extra)
+            if !was_pellet {
+                gs.spawn_new_dot();
+            }
         }
     }
+    count
 }
 
-// Phase 5: Player vs Player collision handler
-fn handle_player_player_collision(gs: &mut GameState) {
-    let ids: Vec<u64> = gs.players.keys().cloned().collect();
-    let mut to_remove = Vec::new();
+// Pellets that reach a virus feed it; enough of them make it fire a new virus
+fn handle_virus_feeding(gs: &mut GameState) {
+    let mut fed = Vec::new();
+    for v in gs.viruses.values() {
+        for did in gs.dot_grid.query(v.x, v.y, v.radius) {
+            if gs.pellets.contains(&did) {
+                fed.push((v.id, did));
+            }
+        }
+    }
 
-    for i in 0..ids.len() {
-        for j in (i + 1)..ids.len() {
-            let id_a = ids[i];
-            let id_b = ids[j];
-
-            if let (Some(a), Some(b)) = (gs.players.get(&id_a), gs.players.get(&id_b)) {
-                if cells_collisions_check(a, b) {
-                    // Check if one player can eat the other
-                    let size_threshold = gs.constants.collide_size_fraction;
-                    
-                    if a.radius > b.radius * size_threshold {
-                        // A can eat B
-                        println!("Player {} ate Player {}", id_a, id_b);
-                        to_remove.push((id_a, id_b));
-                    } else if b.radius > a.radius * size_threshold {
-                        // B can eat A
-                        println!("Player {} ate Player {}", id_b, id_a);
-                        to_remove.push((id_b, id_a));
+    let feed_count = gs.config.virus_feed_count;
+    let fire_speed = gs.config.virus_fire_speed;
+    for (vid, did) in fed {
+        if !gs.viruses.contains_key(&vid) {
+            continue;
+        }
+        let Some(pellet) = gs.remove_dot(did) else {
+            continue;
+        };
+        let Some(virus) = gs.viruses.get_mut(&vid) else {
+            continue;
+        };
+        virus.fed += 1;
+        if virus.fed < feed_count {
+            continue;
+        }
+        virus.fed = 0;
+
+        // Fire along the pellet's path (or away from it once it stopped)
+        let (dx, dy) = if pellet.vx != 0.0 || pellet.vy != 0.0 {
+            (pellet.vx, pellet.vy)
+        } else {
+            (virus.x - pellet.x, virus.y - pellet.y)
+        };
+        let mag = (dx * dx + dy * dy).sqrt();
+        let (nx, ny) = if mag > 0.0 { (dx / mag, dy / mag) } else { (1.0, 0.0) };
+        let (x, y) = (virus.x + nx * 2.0 * virus.radius, virus.y + ny * 2.0 * virus.radius);
+        let (x, y) = (
+            x.clamp(virus.radius, gs.constants.world_width - virus.radius),
+            y.clamp(virus.radius, gs.constants.world_height - virus.radius),
+        );
+        if gs.add_virus(x, y, nx * fire_speed, ny * fire_speed) {
+            debug!(virus = vid, "Virus fired a new virus");
+        }
+    }
+}
+
+// Cells bigger than a virus burst when they cover it, returns the number of pops
+fn handle_virus_pops(gs: &mut GameState) -> u64 {
+    let mut count = 0;
+    let size_threshold = gs.constants.collide_size_fraction;
+    let mut pops = Vec::new();
+    for (pid, p) in gs.players.iter() {
+        for cell in &p.cells {
+            if let Some(v) = gs.viruses.values().find(|v| virus_pops_cell(cell, v, size_threshold)) {
+                pops.push((*pid, cell.id, v.id));
+            }
+        }
+    }
+
+    for (pid, cell_id, vid) in pops {
+        // One virus pops one cell
+        if gs.viruses.remove(&vid).is_none() {
+            continue;
+        }
+        let pieces = gs.pop_cell_on_virus(pid, cell_id);
+        debug!(player = pid, virus = vid, pieces, "Player popped on a virus");
+        count += 1;
+
+        // Keep the base virus population
+        if gs.viruses.len() < gs.config.virus_count {
+            gs.spawn_virus();
+        }
+    }
+    count
+}
+
+// Phase 5: Cell vs Cell collision handler (cells of different players),
+// returns the number of cells eaten
+fn handle_player_player_collision(gs: &mut GameState) -> u64 {
+    let mut count = 0;
+    // Candidate pairs from the player grid, each pair once (lower cell id first);
+    // teammates never eat each other
+    let mut pairs = Vec::new();
+    for (id_a, a) in gs.players.iter() {
+        for cell_a in &a.cells {
+            for cell_b_id in gs.player_grid.query(cell_a.x, cell_a.y, cell_a.radius) {
+                match gs.cell_owner.get(&cell_b_id) {
+                    Some(id_b) if id_b != id_a && cell_b_id > cell_a.id && !gs.same_team(*id_a, *id_b) => {
+                        pairs.push(((*id_a, cell_a.id), (*id_b, cell_b_id)));
                     }
+                    _ => {}
                 }
             }
         }
     }
 
-    // Apply consumption effects
-    for (eater_id, eaten_id) in to_remove {
-        if let Some(eaten) = gs.players.get(&eaten_id) {
-            let eaten_score = eaten.score;
-            
-            // Respawn eaten player instead of removing (player stays connected)
-            gs.respawn_player(eaten_id);
-            
-            // Update eater
-            if let Some(eater) = gs.players.get_mut(&eater_id) {
-                eater.score += eaten_score;
-                // Recalculate radius from score
-                eater.radius = shared::mechanics::calculate_radius_from_score(
-                    eater.score,
-                    10.0 // base radius
-                );
+    let find_cell = |gs: &GameState, (pid, cid): (u64, u64)| {
+        gs.players.get(&pid).and_then(|p| p.cells.iter().find(|c| c.id == cid)).cloned()
+    };
+
+    let mut to_remove = Vec::new();
+    let size_threshold = gs.constants.collide_size_fraction;
+    for (a_key, b_key) in pairs {
+        if let (Some(a), Some(b)) = (find_cell(gs, a_key), find_cell(gs, b_key)) {
+            if cells_collisions_check(&a, &b) {
+                // Check if one cell can eat the other
+                if a.radius > b.radius * size_threshold {
+                    // A can eat B
+                    to_remove.push((a_key, b_key));
+                } else if b.radius > a.radius * size_threshold {
+                    // B can eat A
+                    to_remove.push((b_key, a_key));
+                }
+            }
+        }
+    }
+
+    // Apply consumption effects; a cell eaten earlier this tick eats nothing
+    let base_radius = gs.config.base_radius;
+    for (eater_key, eaten_key) in to_remove {
+        let (Some(_), Some(eaten)) = (find_cell(gs, eater_key), find_cell(gs, eaten_key)) else {
+            continue;
+        };
+        let ((eater_id, eater_cell), (eaten_id, _)) = (eater_key, eaten_key);
+        debug!(eater = eater_id, eaten = eaten_id, "Player ate a cell");
+        count += 1;
+
+        if let Some(eater) = gs.players.get_mut(&eater_id) {
+            if let Some(cell) = eater.cells.iter_mut().find(|c| c.id == eater_cell) {
+                consume_calculation(cell, &eaten, base_radius);
             }
+            refresh_player(eater, base_radius);
+        }
+
+        let mut lost_all = false;
+        if let Some(victim) = gs.players.get_mut(&eaten_id) {
+            victim.cells.retain(|c| c.id != eaten.id);
+            lost_all = victim.cells.is_empty();
+            if !lost_all {
+                refresh_player(victim, base_radius);
+            }
+        }
+        gs.record_cell_eaten(eater_id, eaten_id, lost_all);
+
+        // Respawn eaten player instead of removing (player stays connected)
+        if lost_all {
+            info!(eater = eater_id, eaten = eaten_id, "Player ate a player");
+            gs.respawn_player(eaten_id);
         }
     }
+    gs.sync_player_grid();
+    count
 }
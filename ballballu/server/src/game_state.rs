@@ -1,19 +1,68 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 use rand::Rng;
+use tracing::{debug, info, trace, warn};
 
+use std::sync::Arc;
+use crate::admin::BanList;
+use crate::bots::{self, BOT_ID_BASE};
+use crate::config::{DotType, GameConfig};
+use crate::hall_of_fame::LifeRecord;
+use shared::spatial::SpatialGrid;
 use shared::{
     GameConstant,
     GameSnapshot,
     GameStatus,
+    LobbyState,
+    MovementMode,
+    RoundState,
     protocol::ClientMessage,
-    objects::{PlayerSpec, Dot},
+    objects::{PlayerSpec, Cell, Dot, Virus, LeaderboardEntry, LobbyEntry, RoundResult, TeamScore},
+    mechanics::{split_cells, pop_cell, decay_cell, calculate_radius_from_score, SplitRules, eject_from_cell, refresh_player, update_dot_position, update_virus_position, steer_player, start_step},
 };
 
 #[derive(Debug, Clone)]
 struct PlayerInput {
     pub dx: f32,
     pub dy: f32,
-    pub pending_move: Option<(f32, f32, f32)>, // (dx, dy, distance) for next move
+    pub pending_move: Option<(f32, f32, f32)>, // (dx, dy, distance), starts once the running step ends
+    pub split_requested: bool,                 // split on the next tick
+    pub eject_requested: bool,                 // eject mass on the next tick
+    pub facing: (f32, f32),                    // direction of the last move, new halves fly this way
+    pub last_input_seq: u64,                   // newest Input / Move taken, echoed in PlayerSpec once applied
+}
+
+impl PlayerInput {
+    fn new() -> Self {
+        Self { dx: 0.0, dy: 0.0, pending_move: None, split_requested: false, eject_requested: false, facing: (1.0, 0.0), last_input_seq: 0 }
+    }
+}
+
+/// Disconnected player waiting for its client to resume
+/// Out of the world while parked: not moving, not drawn, cannot be eaten.
+#[derive(Debug, Clone)]
+struct ParkedPlayer {
+    player: PlayerSpec,
+    ready: bool,
+    until_tick: u64, // removed for good at this tick
+}
+
+/// What a player did this round, for the results screen
+#[derive(Debug, Clone, Default)]
+pub struct RoundStats {
+    pub peak_score: u32,
+    pub dots_eaten: u32,
+    pub cells_eaten: u32,
+    pub times_eaten: u32,
+}
+
+/// One life of a player (spawn until eaten or gone), for the hall of fame
+#[derive(Debug, Clone, Default)]
+struct Life {
+    peak_score: u32,
+    players_eaten: u32,
+    dots_eaten: u32,
+    alive_ticks: u64, // ticks alive while the round was running
 }
 
 const RANDOM_NAMES: &[&str] = &[
@@ -26,46 +75,144 @@ const RANDOM_NAMES: &[&str] = &[
     "Bee", "Ant", "Crab", "Turtle", "Monkey", "Otter",
 ];
 
+// Grid cell sizes (world units): dots are tiny, players range from 10 to a few hundred
+const DOT_GRID_CELL: f32 = 50.0;
+const PLAYER_GRID_CELL: f32 = 100.0;
+
 pub struct GameState {
     pub tick: u64,
     pub status: GameStatus,  // Track current game status
     pub players: HashMap<u64, PlayerSpec>,
     pub dots: HashMap<u64, Dot>,
+    // Spatial indexes over player cells / dots; go through insert_dot/remove_dot
+    // and sync_player_grid so they stay in sync
+    pub player_grid: SpatialGrid, // keyed by cell id, see cell_owner
+    pub cell_owner: HashMap<u64, u64>, // cell id -> player id
+    pub dot_grid: SpatialGrid,
+    pub pellets: HashSet<u64>, // dot ids of ejected mass (not respawned when eaten)
+    pub viruses: HashMap<u64, Virus>, // few of them, no grid needed
     pub constants: GameConstant,
+    pub config: GameConfig,  // Server-only settings (dot table, base radius, ...)
     // Phase 4: Store player inputs separately
     player_inputs: HashMap<u64, PlayerInput>,
     ready_players: HashMap<u64, bool>,  // Track which players are ready to start
+    countdown_until_tick: Option<u64>,  // lobby countdown running, game starts at this tick
+    round_stats: HashMap<u64, RoundStats>, // this round, reset when the next one starts
+    lives: HashMap<u64, Life>,          // current life of every player
+    pub finished_lives: Vec<LifeRecord>, // ended lives of humans, drained by the game loop
+    round: RoundState,                  // number, end / reset ticks and the last results
+    bots: Vec<u64>,                     // player ids of the bots, oldest first
+    next_bot_id: u64,
     next_dot_id: u64,
+    next_cell_id: u64,
+    next_virus_id: u64,
+    decay_carry: HashMap<u64, f32>, // cell id -> fractional score still to decay
+    parked: HashMap<u64, ParkedPlayer>, // disconnected players within the resume grace period
+    resume_tokens: HashMap<String, u64>, // token -> player id
+    pub bans: Arc<BanList>,              // shared with the room manager; banned names are refused
 }
 
 impl GameState {
-    pub fn new(constants: GameConstant) -> Self {
+    pub fn new(config: GameConfig) -> Self {
+        let constants = config.to_constants();
+        let initial_dot_count = config.initial_dot_count;
         let mut gs = Self {
             tick: 0,
             status: GameStatus::WaitingToStart,  // Start in waiting state
             players: HashMap::new(),
             dots: HashMap::new(),
+            player_grid: SpatialGrid::new(PLAYER_GRID_CELL),
+            cell_owner: HashMap::new(),
+            dot_grid: SpatialGrid::new(DOT_GRID_CELL),
+            pellets: HashSet::new(),
+            viruses: HashMap::new(),
             constants,
+            config,
             player_inputs: HashMap::new(),
             ready_players: HashMap::new(),
+            countdown_until_tick: None,
+            round_stats: HashMap::new(),
+            lives: HashMap::new(),
+            finished_lives: Vec::new(),
+            round: RoundState::default(),
+            bots: Vec::new(),
+            next_bot_id: BOT_ID_BASE,
             next_dot_id: 1,
+            next_cell_id: 1,
+            next_virus_id: 1,
+            decay_carry: HashMap::new(),
+            parked: HashMap::new(),
+            resume_tokens: HashMap::new(),
+            bans: Arc::default(),
         };
         // Phase 5: Initialize dots
-        gs.spawn_initial_dots(150);
+        gs.spawn_initial_dots(initial_dot_count);
+        for _ in 0..gs.config.virus_count {
+            gs.spawn_virus();
+        }
         gs
     }
 
     fn random_anonymous_name(&self) -> String {
+        format!("Anonymous{}", Self::random_name_word())
+    }
+
+    fn random_name_word() -> &'static str {
         use rand::seq::SliceRandom;
 
-        let mut rng = rand::thread_rng();
-        let word = RANDOM_NAMES.choose(&mut rng).unwrap();
-        format!("Anonymous{}", word)
+        RANDOM_NAMES.choose(&mut rand::thread_rng()).unwrap()
+    }
+
+    /// True if a circle at (x, y) overlaps no player, dot or virus
+    fn position_is_free(&self, x: f32, y: f32, radius: f32) -> bool {
+        !self.player_grid.any_overlap(x, y, radius)
+            && !self.dot_grid.any_overlap(x, y, radius)
+            && !self.viruses.values().any(|v| {
+                let reach = v.radius + radius;
+                (v.x - x).powi(2) + (v.y - y).powi(2) < reach * reach
+            })
+    }
+
+    /// Add a dot to the world and the dot grid
+    pub fn insert_dot(&mut self, dot: Dot) {
+        self.dot_grid.insert(dot.id, dot.x, dot.y, dot.radius);
+        self.dots.insert(dot.id, dot);
     }
 
-    /// Helper function to calculate distance between two points
-    fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
-        ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
+    /// Remove a dot from the world and the dot grid
+    pub fn remove_dot(&mut self, id: u64) -> Option<Dot> {
+        self.dot_grid.remove(id);
+        self.pellets.remove(&id);
+        self.dots.remove(&id)
+    }
+
+    /// Re-index every player cell after movement / growth / splits
+    /// Cells come and go every tick, so the index is rebuilt from scratch
+    pub fn sync_player_grid(&mut self) {
+        self.player_grid.clear();
+        self.cell_owner.clear();
+        for p in self.players.values() {
+            for cell in &p.cells {
+                self.player_grid.insert(cell.id, cell.x, cell.y, cell.radius);
+                self.cell_owner.insert(cell.id, p.id);
+            }
+        }
+    }
+
+    /// A fresh single cell for a (re)spawning player
+    fn new_cell(&mut self, x: f32, y: f32) -> Cell {
+        let id = self.next_cell_id;
+        self.next_cell_id += 1;
+        Cell {
+            id,
+            x,
+            y,
+            radius: self.config.base_radius,
+            score: 0,
+            boost_vx: 0.0,
+            boost_vy: 0.0,
+            merge_tick: 0,
+        }
     }
 
     /// Find an empty position that doesn't overlap with any players or dots
@@ -76,8 +223,8 @@ impl GameState {
         max_attempts: usize,
     ) -> Option<(f32, f32)> {
         let mut rng = rand::thread_rng();
-        let world_width = 2000.0;
-        let world_height = 2000.0;
+        let world_width = self.constants.world_width;
+        let world_height = self.constants.world_height;
         let min_x = radius;
         let max_x = world_width - radius;
         let min_y = radius;
@@ -87,36 +234,11 @@ impl GameState {
             let x = rng.gen_range(min_x..max_x);
             let y = rng.gen_range(min_y..max_y);
 
-            // Check collision with players
-            let mut collides = false;
-            for player in self.players.values() {
-                let d = Self::distance(x, y, player.x, player.y);
-                if d < (radius + player.radius) {
-                    collides = true;
-                    break;
-                }
-            }
-
-            if collides {
-                continue;
-            }
-
-            // Check collision with dots
-            for dot in self.dots.values() {
-                let d = Self::distance(x, y, dot.x, dot.y);
-                if d < (radius + dot.radius) {
-                    collides = true;
-                    break;
-                }
-            }
-
-            if !collides {
+            if self.position_is_free(x, y, radius) {
                 return Some((x, y));
             }
         }
-
-        // Fallback: return center position if all attempts failed
-        Some((world_width / 2.0, world_height / 2.0))
+        None
     }
 
     /// Phase 5: Spawn initial dots on the map
@@ -124,44 +246,41 @@ impl GameState {
     fn spawn_initial_dots(&mut self, count: usize) {
         let mut rng = rand::thread_rng();
         
-        // Dot configurations: (score, color, radius)
-        let dot_configs = [
-            (2, (100, 150, 255), 4.0),   // Blue, small
-            (5, (255, 255, 100), 6.0),   // Yellow, medium
-            (10, (255, 100, 100), 8.0),  // Red, large
-        ];
-        
         for _ in 0..count {
             // Randomly select a dot type
-            let config = dot_configs[rng.gen_range(0..dot_configs.len())];
-            let (score, color, radius) = config;
+            let dot_types = &self.config.dot_types;
+            let DotType { score, color, radius } = dot_types[rng.gen_range(0..dot_types.len())];
             
             // Find empty position for this dot
             if let Some((x, y)) = self.find_empty_position(radius, 100) {
                 let id = self.next_dot_id;
                 self.next_dot_id += 1;
-                self.dots.insert(id, Dot {
+                self.insert_dot(Dot {
                     id,
                     x,
                     y,
                     radius,
                     color,
                     score,
+                    vx: 0.0,
+                    vy: 0.0,
                 });
             } else {
                 // If can't find empty position, still create dot at random position
                 // (shouldn't happen often with 150 dots in 2000x2000 world)
-                let world_width = 2000.0;
-                let world_height = 2000.0;
+                let world_width = self.constants.world_width;
+                let world_height = self.constants.world_height;
                 let id = self.next_dot_id;
                 self.next_dot_id += 1;
-                self.dots.insert(id, Dot {
+                self.insert_dot(Dot {
                     id,
                     x: rng.gen_range(radius..(world_width - radius)),
                     y: rng.gen_range(radius..(world_height - radius)),
                     radius,
                     color,
                     score,
+                    vx: 0.0,
+                    vy: 0.0,
                 });
             }
         }
@@ -172,67 +291,45 @@ impl GameState {
     pub fn spawn_new_dot(&mut self) -> bool {
         let mut rng = rand::thread_rng();
         
-        // Dot configurations: (score, color, radius)
-        let dot_configs = [
-            (2, (100, 150, 255), 4.0),   // Blue, small
-            (5, (255, 255, 100), 6.0),   // Yellow, medium
-            (10, (255, 100, 100), 8.0),  // Red, large
-        ];
-        
         // Randomly select a dot type
-        let config = dot_configs[rng.gen_range(0..dot_configs.len())];
-        let (score, color, radius) = config;
+        let dot_types = &self.config.dot_types;
+        let DotType { score, color, radius } = dot_types[rng.gen_range(0..dot_types.len())];
         
         // Find empty position for this dot
         if let Some((x, y)) = self.find_empty_position(radius, 100) {
             let id = self.next_dot_id;
             self.next_dot_id += 1;
-            self.dots.insert(id, Dot {
+            self.insert_dot(Dot {
                 id,
                 x,
                 y,
                 radius,
                 color,
                 score,
+                vx: 0.0,
+                vy: 0.0,
             });
             true
         } else {
             // If can't find empty position, try a few more times with random positions
-            let world_width = 2000.0;
-            let world_height = 2000.0;
+            let world_width = self.constants.world_width;
+            let world_height = self.constants.world_height;
             for _ in 0..10 {
                 let x = rng.gen_range(radius..(world_width - radius));
                 let y = rng.gen_range(radius..(world_height - radius));
                 
-                // Quick check if position is empty
-                let mut collides = false;
-                for player in self.players.values() {
-                    let d = Self::distance(x, y, player.x, player.y);
-                    if d < (radius + player.radius) {
-                        collides = true;
-                        break;
-                    }
-                }
-                if !collides {
-                    for dot in self.dots.values() {
-                        let d = Self::distance(x, y, dot.x, dot.y);
-                        if d < (radius + dot.radius) {
-                            collides = true;
-                            break;
-                        }
-                    }
-                }
-                
-                if !collides {
+                if self.position_is_free(x, y, radius) {
                     let id = self.next_dot_id;
                     self.next_dot_id += 1;
-                    self.dots.insert(id, Dot {
+                    self.insert_dot(Dot {
                         id,
                         x,
                         y,
                         radius,
                         color,
                         score,
+                        vx: 0.0,
+                        vy: 0.0,
                     });
                     return true;
                 }
@@ -241,11 +338,89 @@ impl GameState {
         }
     }
 
+    /// Spawn a virus at an empty position
+    /// Returns false once the world holds `virus_max` viruses, or when no free
+    /// spot turns up in a crowded world
+    pub fn spawn_virus(&mut self) -> bool {
+        let radius = self.config.virus_radius;
+        let Some((x, y)) = self.find_empty_position(radius, 100) else {
+            return false;
+        };
+        self.add_virus(x, y, 0.0, 0.0)
+    }
+
+    /// Add a virus (possibly moving), unless the world is full of them
+    pub fn add_virus(&mut self, x: f32, y: f32, vx: f32, vy: f32) -> bool {
+        if self.viruses.len() >= self.config.virus_max {
+            return false;
+        }
+        let id = self.next_virus_id;
+        self.next_virus_id += 1;
+        let radius = self.config.virus_radius;
+        self.viruses.insert(id, Virus { id, x, y, radius, fed: 0, vx, vy });
+        true
+    }
+
+    /// Move fired viruses that are still sliding
+    pub fn move_viruses(&mut self) {
+        let tick_ms = self.constants.tick_interval_ms as f32;
+        let (world_width, world_height) = (self.constants.world_width, self.constants.world_height);
+        for virus in self.viruses.values_mut() {
+            update_virus_position(virus, tick_ms, world_width, world_height);
+        }
+    }
+
+    /// Split / pop rules from the config
+    pub fn split_rules(&self) -> SplitRules {
+        SplitRules {
+            min_score: self.config.min_split_score,
+            max_cells: self.config.max_cells,
+            launch_speed: self.config.split_speed,
+            merge_delay_ticks: self.config.merge_cooldown_ms / self.constants.tick_interval_ms,
+        }
+    }
+
+    /// Burst a player's cell on a virus: the cell eats the virus, then
+    /// pops into `virus_pieces` cells. Returns the number of new cells.
+    pub fn pop_cell_on_virus(&mut self, player_id: u64, cell_id: u64) -> usize {
+        let rules = self.split_rules();
+        let base_radius = self.config.base_radius;
+        let (virus_score, pieces) = (self.config.virus_score, self.config.virus_pieces);
+        let Some(player) = self.players.get_mut(&player_id) else {
+            return 0;
+        };
+        if let Some(cell) = player.cells.iter_mut().find(|c| c.id == cell_id) {
+            cell.score += virus_score;
+            cell.radius = calculate_radius_from_score(cell.score, base_radius);
+        }
+        pop_cell(player, cell_id, pieces, &rules, self.tick, base_radius, &mut self.next_cell_id)
+    }
+
+    /// Team with the fewest players (lowest number on a tie), None in free-for-all
+    fn pick_team(&self) -> Option<u8> {
+        let mut sizes = vec![0usize; self.config.team_count];
+        for p in self.players.values().chain(self.parked.values().map(|parked| &parked.player)) {
+            // Teams beyond `team_count` (kept from before a restart with fewer teams) are not counted
+            if let Some(size) = p.team.and_then(|team| sizes.get_mut(team as usize)) {
+                *size += 1;
+            }
+        }
+        let (team, _) = sizes.iter().enumerate().min_by_key(|(team, size)| (**size, *team))?;
+        Some(team as u8)
+    }
+
+    /// True if both players are on the same team (never in free-for-all)
+    pub fn same_team(&self, a: u64, b: u64) -> bool {
+        let team = |id| self.players.get(&id).and_then(|p| p.team);
+        team(a).is_some() && team(a) == team(b)
+    }
+
     /// Add new player when connected
     pub fn add_player(&mut self, id: u64) {
-        let base_radius = 10.0;
+        let base_radius = self.config.base_radius;
         let (x, y) = self.find_empty_position(base_radius, 100)
-            .unwrap_or((1000.0, 1000.0)); // Fallback to center if all attempts fail
+            .unwrap_or((self.constants.world_width / 2.0, self.constants.world_height / 2.0)); // Fallback to center if all attempts fail
+        let cell = self.new_cell(x, y);
         
         let p = PlayerSpec {
             id,
@@ -259,39 +434,219 @@ impl GameState {
             remaining_distance: 0.0,
             vx: 0.0,
             vy: 0.0,
+            cells: vec![cell],
+            team: self.pick_team(),
+            bot: false,
         };
         self.players.insert(id, p);
+        self.sync_player_grid();
         // Phase 4: Initialize input to zero
-        self.player_inputs.insert(id, PlayerInput { dx: 0.0, dy: 0.0, pending_move: None });
+        self.player_inputs.insert(id, PlayerInput::new());
         // Mark player as not ready (must press space to start)
         self.ready_players.insert(id, false);
-        println!("GameState: Player {} added at ({}, {})", id, x, y);
+        debug!(player = id, x, y, "Player added");
+    }
+
+    /// Add or remove bots so humans + bots make `bot_count` players, then let
+    /// every bot pick its next move (through the same pending move as humans)
+    pub fn update_bots(&mut self) {
+        let target = self.config.bot_count.saturating_sub(self.player_count());
+        while self.bots.len() < target {
+            let id = self.next_bot_id;
+            self.next_bot_id += 1;
+            self.add_player(id);
+            // Bots are always ready, so they are not in the lobby's ready list
+            self.ready_players.remove(&id);
+            if let Some(p) = self.players.get_mut(&id) {
+                p.bot = true;
+                p.name = Self::random_name_word().to_string();
+            }
+            self.bots.push(id);
+        }
+        while self.bots.len() > target {
+            if let Some(id) = self.bots.pop() {
+                self.remove_player(id);
+            }
+        }
+
+        if self.status != GameStatus::Playing {
+            return;
+        }
+        for id in self.bots.clone() {
+            if let Some((dx, dy, distance)) = bots::decide(self, id) {
+                self.queue_move(id, dx, dy, distance);
+            }
+        }
     }
 
     /// Remove player when disconnected
     pub fn remove_player(&mut self, id: u64) {
+        self.end_life(id);
         self.players.remove(&id);
+        self.sync_player_grid();
         self.player_inputs.remove(&id);
         self.ready_players.remove(&id);
-        println!("GameState: Player {} removed", id);
+        self.parked.remove(&id);
+        self.resume_tokens.retain(|_, pid| *pid != id);
+        self.bots.retain(|bot| *bot != id);
+        debug!(player = id, "Player removed");
+    }
+
+    /// True for connected, parked and bot players of this world
+    pub fn has_player(&self, id: u64) -> bool {
+        self.players.contains_key(&id) || self.parked.contains_key(&id)
+    }
+
+    /// Human players in this world, counting disconnected ones that may
+    /// still resume (bots do not take a slot)
+    pub fn player_count(&self) -> usize {
+        self.connected_human_count() + self.parked.len()
+    }
+
+    /// Human players in the world right now (not parked)
+    pub fn connected_human_count(&self) -> usize {
+        self.players.keys().filter(|id| !self.bots.contains(id)).count()
+    }
+
+    /// Disconnected players waiting for a resume
+    pub fn parked_count(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Connected and parked human players
+    pub fn humans(&self) -> impl Iterator<Item = &PlayerSpec> {
+        self.players
+            .values()
+            .filter(|p| !p.bot)
+            .chain(self.parked.values().map(|parked| &parked.player))
+    }
+
+    /// Server-side AI players currently in this world
+    pub fn bot_count(&self) -> usize {
+        self.bots.len()
+    }
+
+    /// New secret a client can use to take this player back after a reconnect
+    pub fn issue_resume_token(&mut self, id: u64) -> String {
+        let token = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        self.resume_tokens.insert(token.clone(), id);
+        token
+    }
+
+    /// Take a disconnected player out of the world for `grace_ticks`
+    /// Returns false if there is no such player.
+    pub fn park_player(&mut self, id: u64, grace_ticks: u64) -> bool {
+        let Some(player) = self.players.remove(&id) else {
+            return false;
+        };
+        self.sync_player_grid();
+        self.player_inputs.remove(&id);
+        let ready = self.ready_players.remove(&id).unwrap_or(false);
+        let until_tick = self.tick + grace_ticks;
+        self.parked.insert(id, ParkedPlayer { player, ready, until_tick });
+        debug!(player = id, until_tick, "Player parked");
+        true
+    }
+
+    /// Player id for a resume token, putting a parked player back into the world
+    /// A player that is still connected is returned as is (the new connection
+    /// takes over). None if the token is unknown or the player expired.
+    pub fn resume_player(&mut self, token: &str) -> Option<u64> {
+        let id = *self.resume_tokens.get(token)?;
+        if let Some(parked) = self.parked.remove(&id) {
+            self.players.insert(id, parked.player);
+            self.sync_player_grid();
+            self.ready_players.insert(id, parked.ready);
+            debug!(player = id, "Player resumed");
+        } else if !self.players.contains_key(&id) {
+            return None;
+        }
+        // The new connection starts its input sequence from scratch
+        self.player_inputs.insert(id, PlayerInput::new());
+        Some(id)
+    }
+
+    /// Drop parked players whose grace period is over
+    pub fn expire_parked_players(&mut self) {
+        let expired: Vec<u64> = self
+            .parked
+            .iter()
+            .filter(|(_, parked)| parked.until_tick <= self.tick)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            self.end_life(id);
+            self.parked.remove(&id);
+            self.resume_tokens.retain(|_, pid| *pid != id);
+            info!(player = id, "Parked player expired");
+        }
+    }
+
+    /// Back to a single fresh cell at a random empty position
+    fn reset_player_spec(&mut self, player: &mut PlayerSpec) {
+        let base_radius = self.config.base_radius;
+        let (x, y) = self.find_empty_position(base_radius, 100)
+            .unwrap_or((self.constants.world_width / 2.0, self.constants.world_height / 2.0)); // Fallback to center if all attempts fail
+        let cell = self.new_cell(x, y);
+
+        player.x = x;
+        player.y = y;
+        player.radius = base_radius;
+        player.score = 0;
+        player.speed = self.constants.move_speed_base;
+        player.remaining_distance = 0.0;
+        player.vx = 0.0;
+        player.vy = 0.0;
+        player.cells = vec![cell];
+    }
+
+    /// A player was eaten or left: queue its life for the hall of fame
+    /// Bots and lives that never saw a running round are not recorded.
+    fn end_life(&mut self, id: u64) {
+        let Some(life) = self.lives.remove(&id) else {
+            return;
+        };
+        let player = self.players.get(&id).or_else(|| self.parked.get(&id).map(|parked| &parked.player));
+        let Some(player) = player.filter(|p| !p.bot) else {
+            return;
+        };
+        if life.alive_ticks == 0 {
+            return;
+        }
+        self.finished_lives.push(LifeRecord {
+            name: player.name.clone(),
+            peak_score: life.peak_score.max(player.score),
+            players_eaten: life.players_eaten,
+            dots_eaten: life.dots_eaten,
+            survival_ms: life.alive_ticks * self.constants.tick_interval_ms,
+            ended_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        });
+    }
+
+    /// A cell of `id` ate a dot or pellet
+    pub fn record_dot_eaten(&mut self, id: u64) {
+        self.round_stats.entry(id).or_default().dots_eaten += 1;
+        self.lives.entry(id).or_default().dots_eaten += 1;
+    }
+
+    /// A cell of `eater` ate a cell of `eaten`; `lost_all` if that was its last one
+    pub fn record_cell_eaten(&mut self, eater: u64, eaten: u64, lost_all: bool) {
+        self.round_stats.entry(eater).or_default().cells_eaten += 1;
+        if lost_all {
+            self.round_stats.entry(eaten).or_default().times_eaten += 1;
+            self.lives.entry(eater).or_default().players_eaten += 1;
+        }
     }
 
     /// Respawn a player after being eaten (resets to initial state at random position)
     pub fn respawn_player(&mut self, id: u64) {
-        let base_radius = 10.0;
-        let (x, y) = self.find_empty_position(base_radius, 100)
-            .unwrap_or((1000.0, 1000.0)); // Fallback to center if all attempts fail
-        
-        if let Some(player) = self.players.get_mut(&id) {
-            player.x = x;
-            player.y = y;
-            player.radius = base_radius;
-            player.score = 0;
-            player.speed = self.constants.move_speed_base;
-            player.remaining_distance = 0.0;
-            player.vx = 0.0;
-            player.vy = 0.0;
-            println!("GameState: Player {} respawned at ({}, {})", id, x, y);
+        self.end_life(id);
+        if let Some(mut player) = self.players.remove(&id) {
+            self.sync_player_grid();
+            self.reset_player_spec(&mut player);
+            debug!(player = id, x = player.x, y = player.y, "Player respawned");
+            self.players.insert(id, player);
+            self.sync_player_grid();
         }
         
         // Reset input state
@@ -299,6 +654,8 @@ impl GameState {
             player_input.dx = 0.0;
             player_input.dy = 0.0;
             player_input.pending_move = None;
+            player_input.split_requested = false;
+            player_input.eject_requested = false;
         }
     }
 
@@ -306,82 +663,481 @@ impl GameState {
     pub fn handle_message(&mut self, id: u64, msg: ClientMessage) {
         match msg {
             ClientMessage::Join { name } => {
+                if self.bans.is_name_banned(&name) {
+                    info!(player = id, name = %name.trim(), "Banned name refused");
+                    return;
+                }
                 if let Some(p) = self.players.get_mut(&id) {
                     let trimmed = name.trim();
                     if !trimmed.is_empty() {
                         p.name = trimmed.to_string();
                     }
-                    println!("GameState: Player {} final name = {}", id, p.name);
+                    info!(player = id, name = %p.name, "Player joined");
                 }
             }
             ClientMessage::Input { input } => {
-                // Input message not used in current implementation
-                println!("GameState: Player {} sent deprecated Input message: dx={}, dy={}", 
-                    id, input.dx, input.dy);
+                // Steering only exists in continuous mode; drop stale / reordered inputs
+                if self.constants.movement_mode != MovementMode::Continuous {
+                    return;
+                }
+                if let Some(player_input) = self.player_inputs.get_mut(&id) {
+                    if input.sequence_number <= player_input.last_input_seq {
+                        return;
+                    }
+                    player_input.last_input_seq = input.sequence_number;
+                    player_input.dx = input.dx;
+                    player_input.dy = input.dy;
+                    if input.dx != 0.0 || input.dy != 0.0 {
+                        player_input.facing = (input.dx, input.dy);
+                    }
+                }
             }
-            ClientMessage::Move { dx, dy, distance } => {
+            ClientMessage::Move { dx, dy, distance, sequence_number } => {
+                // Drop stale / reordered moves like stale Inputs
+                if let Some(player_input) = self.player_inputs.get_mut(&id) {
+                    if sequence_number <= player_input.last_input_seq {
+                        return;
+                    }
+                    player_input.last_input_seq = sequence_number;
+                }
                 // Store the move command to be processed next tick
+                if self.queue_move(id, dx, dy, distance) {
+                    trace!(player = id, dx, dy, distance, "Move queued");
+                }
+            }
+            ClientMessage::Split => {
                 if let Some(player_input) = self.player_inputs.get_mut(&id) {
-                    player_input.pending_move = Some((dx, dy, distance));
-                    println!("GameState: Player {} queued move: dx={}, dy={}, distance={}", id, dx, dy, distance);
+                    player_input.split_requested = true;
+                }
+            }
+            ClientMessage::EjectMass => {
+                if let Some(player_input) = self.player_inputs.get_mut(&id) {
+                    player_input.eject_requested = true;
                 }
             }
             ClientMessage::Ready => {
-                // Mark player as ready to start
+                // Mark player as ready to start (the lobby starts the game, see update_lobby)
                 self.ready_players.insert(id, true);
-                println!("GameState: Player {} is ready", id);
-                
-                // Check if all players are ready (and at least 1 player connected)
-                if self.all_players_ready() && !self.players.is_empty() {
-                    self.status = GameStatus::Playing;
-                    println!("GameState: All players ready! Starting game!");
+                debug!(player = id, "Player is ready");
+            }
+            ClientMessage::ChooseTeam { team } => {
+                if self.status == GameStatus::Playing || team as usize >= self.config.team_count {
+                    return;
+                }
+                if let Some(p) = self.players.get_mut(&id) {
+                    p.team = Some(team);
+                    debug!(player = id, team, "Player switched team");
+                }
+            }
+            ClientMessage::Unready => {
+                if self.status == GameStatus::WaitingToStart && self.players.contains_key(&id) {
+                    self.ready_players.insert(id, false);
+                    debug!(player = id, "Player is no longer ready");
                 }
             }
+            ClientMessage::Ack { .. } => {
+                // Snapshot acks only matter to the broadcaster (handled in websocket_manager)
+            }
+            ClientMessage::Resume { .. }
+            | ClientMessage::ListRooms
+            | ClientMessage::JoinRoom { .. }
+            | ClientMessage::HallOfFame => {
+                // Rebinding the connection / rooms / hall of fame happen in websocket_manager
+            }
             ClientMessage::Quit => {
                 // Quit is now handled in websocket_manager, this should not be reached
-                println!("GameState: Player {} sent Quit (should be handled by websocket_manager)", id);
+                warn!(player = id, "Quit reached the game state (should be handled by websocket_manager)");
             }
         }
     }
 
-    /// Check if all connected players are ready
+    /// Queue a discrete move for the next tick (replaces any queued move)
+    /// Returns false if the player is unknown
+    pub fn queue_move(&mut self, id: u64, dx: f32, dy: f32, distance: f32) -> bool {
+        match self.player_inputs.get_mut(&id) {
+            Some(player_input) => {
+                player_input.pending_move = Some((dx, dy, distance));
+                if dx != 0.0 || dy != 0.0 {
+                    player_input.facing = (dx, dy);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check if all connected players are ready (bots always are)
     pub fn all_players_ready(&self) -> bool {
-        if self.players.is_empty() {
+        if self.connected_human_count() == 0 {
             return false;
         }
-        self.players.keys().all(|id| {
-            self.ready_players.get(id).copied().unwrap_or(false)
+        self.players.values().filter(|p| !p.bot).all(|p| {
+            self.ready_players.get(&p.id).copied().unwrap_or(false)
         })
     }
 
+    /// Lobby phase: start the countdown once enough players are all ready,
+    /// cancel it when that stops being true, start the game when it runs out
+    pub fn update_lobby(&mut self) {
+        if self.status != GameStatus::WaitingToStart {
+            self.countdown_until_tick = None;
+            return;
+        }
+        let humans = self.connected_human_count();
+        let can_start = humans >= self.config.lobby_min_players && self.all_players_ready();
+        match (can_start, self.countdown_until_tick) {
+            (true, None) => {
+                let ticks = self.config.lobby_countdown_ms / self.constants.tick_interval_ms;
+                self.countdown_until_tick = Some(self.tick + ticks);
+                info!(players = humans, countdown_ms = self.config.lobby_countdown_ms, "All players ready, countdown started");
+            }
+            (false, Some(_)) => {
+                self.countdown_until_tick = None;
+                info!("Countdown cancelled");
+            }
+            _ => {}
+        }
+        if self.countdown_until_tick.is_some_and(|start| self.tick >= start) {
+            self.countdown_until_tick = None;
+            self.start_round();
+        }
+    }
+
+    /// Lobby is over: play a new round
+    fn start_round(&mut self) {
+        self.status = GameStatus::Playing;
+        self.round_stats.clear();
+        let ticks = self.config.round_duration_ms / self.constants.tick_interval_ms;
+        self.round = RoundState {
+            number: self.round.number + 1,
+            ends_at_tick: (ticks > 0).then_some(self.tick + ticks),
+            next_round_at_tick: None,
+            results: Vec::new(),
+        };
+        info!(round = self.round.number, "Round started");
+    }
+
+    /// Round timer: end the round when time is up, reset the world once the
+    /// results have been shown long enough
+    pub fn update_round(&mut self) {
+        match self.status {
+            GameStatus::Playing => {
+                for p in self.players.values() {
+                    let stats = self.round_stats.entry(p.id).or_default();
+                    stats.peak_score = stats.peak_score.max(p.score);
+                    let life = self.lives.entry(p.id).or_default();
+                    life.peak_score = life.peak_score.max(p.score);
+                    life.alive_ticks += 1;
+                }
+                if self.round.ends_at_tick.is_some_and(|end| self.tick >= end) {
+                    self.end_round();
+                }
+            }
+            GameStatus::GameOver => {
+                if self.round.next_round_at_tick.is_some_and(|next| self.tick >= next) {
+                    self.reset_round();
+                }
+            }
+            GameStatus::WaitingToStart => {}
+        }
+    }
+
+    /// Time is up: rank everybody (parked players too) and show the results
+    fn end_round(&mut self) {
+        let mut ranked: Vec<&PlayerSpec> = self
+            .players
+            .values()
+            .chain(self.parked.values().map(|parked| &parked.player))
+            .collect();
+        ranked.sort_by(|a, b| b.score.cmp(&a.score).then(a.id.cmp(&b.id)));
+        let results: Vec<RoundResult> = ranked
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let stats = self.round_stats.get(&p.id).cloned().unwrap_or_default();
+                RoundResult {
+                    rank: i + 1,
+                    id: p.id,
+                    name: p.name.clone(),
+                    score: p.score,
+                    peak_score: stats.peak_score.max(p.score),
+                    dots_eaten: stats.dots_eaten,
+                    cells_eaten: stats.cells_eaten,
+                    times_eaten: stats.times_eaten,
+                    team: p.team,
+                    bot: p.bot,
+                }
+            })
+            .collect();
+        if let Some(winner) = results.first() {
+            info!(round = self.round.number, winner = %winner.name, score = winner.score, "Round over");
+        }
+
+        let ticks = self.config.results_duration_ms / self.constants.tick_interval_ms;
+        self.status = GameStatus::GameOver;
+        self.round.ends_at_tick = None;
+        self.round.next_round_at_tick = Some(self.tick + ticks.max(1));
+        self.round.results = results;
+    }
+
+    /// Fresh world for the next round: new dots and viruses, every player
+    /// back to a single cell. The room returns to the lobby with the ready
+    /// flags kept, so the countdown starts again by itself.
+    pub fn reset_round(&mut self) {
+        let dot_ids: Vec<u64> = self.dots.keys().copied().collect();
+        for id in dot_ids {
+            self.remove_dot(id);
+        }
+        self.viruses.clear();
+        self.decay_carry.clear();
+        self.spawn_initial_dots(self.config.initial_dot_count);
+        for _ in 0..self.config.virus_count {
+            self.spawn_virus();
+        }
+
+        let ids: Vec<u64> = self.players.keys().copied().collect();
+        for id in ids {
+            self.respawn_player(id);
+        }
+        let mut parked = std::mem::take(&mut self.parked);
+        for p in parked.values_mut() {
+            self.reset_player_spec(&mut p.player);
+        }
+        self.parked = parked;
+
+        self.round_stats.clear();
+        self.round.next_round_at_tick = None;
+        self.round.results.clear();
+        self.status = GameStatus::WaitingToStart;
+        info!("World reset, back to the lobby");
+    }
+
+    /// Admin override of the game status
+    /// Playing starts a new round, GameOver ends the current one with results,
+    /// WaitingToStart goes back to the lobby keeping the world as it is.
+    pub fn force_status(&mut self, status: GameStatus) {
+        match status {
+            GameStatus::Playing => self.start_round(),
+            GameStatus::GameOver => self.end_round(),
+            GameStatus::WaitingToStart => {
+                self.status = GameStatus::WaitingToStart;
+                self.countdown_until_tick = None;
+                self.round.ends_at_tick = None;
+                self.round.next_round_at_tick = None;
+                info!("Back to the lobby");
+            }
+        }
+    }
+
+    /// Switch to changed game settings while the room runs
+    /// Constants go out with the next snapshot; settings only read at world
+    /// creation (like `initial_dot_count`) apply from the next reset.
+    pub fn apply_config(&mut self, config: GameConfig) {
+        self.constants = config.to_constants();
+        self.config = config;
+    }
+
+    /// Current round number, timers and the last results
+    pub fn round_state(&self) -> &RoundState {
+        &self.round
+    }
+
+    /// Lobby roster and countdown for snapshots
+    pub fn lobby_state(&self) -> LobbyState {
+        let mut roster: Vec<LobbyEntry> = self
+            .players
+            .values()
+            .filter(|p| !p.bot)
+            .map(|p| LobbyEntry {
+                id: p.id,
+                name: p.name.clone(),
+                ready: self.ready_players.get(&p.id).copied().unwrap_or(false),
+                team: p.team,
+            })
+            .collect();
+        roster.sort_by_key(|e| e.id);
+        LobbyState {
+            min_players: self.config.lobby_min_players,
+            countdown_ms: self
+                .countdown_until_tick
+                .map(|start| start.saturating_sub(self.tick) * self.constants.tick_interval_ms),
+            roster,
+        }
+    }
+
     /// Apply pending moves to players
+    /// In continuous mode the latest steering input wins over queued steps.
+    /// A queued step starts when the previous one has ended, so holding an
+    /// arrow key chains steps without gaps.
     pub fn apply_pending_moves(&mut self) {
         let player_ids: Vec<u64> = self.players.keys().cloned().collect();
+        let continuous = self.constants.movement_mode == MovementMode::Continuous;
+        let tick_ms = self.constants.tick_interval_ms as f32;
         
         for id in player_ids {
-            if let Some(player_input) = self.player_inputs.get_mut(&id) {
-                if let Some((dx, dy, distance)) = player_input.pending_move.take() {
-                    // Only start a new move if not currently moving
-                    if let Some(player) = self.players.get_mut(&id) {
-                        if player.remaining_distance <= 0.0 && distance > 0.0 {
-                            let mag = (dx * dx + dy * dy).sqrt();
-                            if mag > 0.0 {
-                                let speed = player.speed;
-                                player.vx = (dx / mag) * speed;
-                                player.vy = (dy / mag) * speed;
-                                player.remaining_distance = distance;
-                            }
-                        }
+            let (Some(player_input), Some(player)) = (self.player_inputs.get_mut(&id), self.players.get_mut(&id)) else {
+                continue;
+            };
+
+            if continuous && (player_input.dx != 0.0 || player_input.dy != 0.0) {
+                // Steering persists until the next Input replaces it
+                player_input.pending_move = None;
+                steer_player(player, player_input.dx, player_input.dy, tick_ms);
+            } else {
+                // A discrete move waits for the running step to end; a newer Move replaces it
+                if player.remaining_distance <= 0.0 {
+                    if let Some((dx, dy, distance)) = player_input.pending_move.take() {
+                        start_step(player, dx, dy, distance);
                     }
                 }
-                
+
                 // Clear stored continuous input (for one-click-one-step model)
                 player_input.dx = 0.0;
                 player_input.dy = 0.0;
             }
+
+            // Echo the newest input once it is applied (client reconciliation);
+            // a Move still waiting for the running step is not acknowledged yet
+            if player_input.pending_move.is_none() {
+                player.sequence_number = player_input.last_input_seq;
+            }
         }
     }
 
+    /// Split every player that asked for it since the last tick
+    pub fn apply_pending_splits(&mut self) {
+        let rules = self.split_rules();
+        let base_radius = self.config.base_radius;
+
+        for (id, player_input) in self.player_inputs.iter_mut() {
+            if !std::mem::take(&mut player_input.split_requested) {
+                continue;
+            }
+            if let Some(player) = self.players.get_mut(id) {
+                let (dx, dy) = player_input.facing;
+                let added = split_cells(player, dx, dy, &rules, self.tick, base_radius, &mut self.next_cell_id);
+                if added > 0 {
+                    debug!(player = id, cells = player.cells.len(), "Player split");
+                }
+            }
+        }
+    }
+
+    /// Eject a pellet from every cell of players that asked for it
+    pub fn apply_pending_ejects(&mut self) {
+        let cfg = &self.config;
+        let (cost, min_score, speed, pellet_radius, color) =
+            (cfg.eject_score, cfg.eject_min_score, cfg.eject_speed, cfg.eject_radius, cfg.eject_color);
+        let base_radius = cfg.base_radius;
+        let (world_width, world_height) = (self.constants.world_width, self.constants.world_height);
+
+        let mut new_pellets = Vec::new();
+        for (id, player_input) in self.player_inputs.iter_mut() {
+            if !std::mem::take(&mut player_input.eject_requested) {
+                continue;
+            }
+            let Some(player) = self.players.get_mut(id) else {
+                continue;
+            };
+            let (dx, dy) = player_input.facing;
+            let mag = (dx * dx + dy * dy).sqrt();
+            let (nx, ny) = if mag > 0.0 { (dx / mag, dy / mag) } else { (1.0, 0.0) };
+
+            for cell in player.cells.iter_mut() {
+                if !eject_from_cell(cell, cost, min_score, base_radius) {
+                    continue;
+                }
+                // Spawn just outside the cell so it is not eaten right back
+                let offset = cell.radius + pellet_radius + 1.0;
+                new_pellets.push(Dot {
+                    id: 0,
+                    x: (cell.x + nx * offset).clamp(pellet_radius, world_width - pellet_radius),
+                    y: (cell.y + ny * offset).clamp(pellet_radius, world_height - pellet_radius),
+                    radius: pellet_radius,
+                    color,
+                    score: cost,
+                    vx: nx * speed,
+                    vy: ny * speed,
+                });
+            }
+            refresh_player(player, base_radius);
+        }
+
+        for mut pellet in new_pellets {
+            pellet.id = self.next_dot_id;
+            self.next_dot_id += 1;
+            self.pellets.insert(pellet.id);
+            self.insert_dot(pellet);
+        }
+    }
+
+    /// Move ejected pellets that are still sliding
+    pub fn move_pellets(&mut self) {
+        let tick_ms = self.constants.tick_interval_ms as f32;
+        let (world_width, world_height) = (self.constants.world_width, self.constants.world_height);
+        for id in &self.pellets {
+            if let Some(dot) = self.dots.get_mut(id) {
+                if dot.vx != 0.0 || dot.vy != 0.0 {
+                    update_dot_position(dot, tick_ms, world_width, world_height);
+                    self.dot_grid.insert(dot.id, dot.x, dot.y, dot.radius);
+                }
+            }
+        }
+    }
+
+    /// Shed score from cells above the decay threshold (one tick worth)
+    pub fn apply_mass_decay(&mut self) {
+        let threshold = self.config.decay_threshold;
+        let fraction = self.config.decay_percent_per_sec / 100.0 * self.constants.tick_interval_ms as f32 / 1000.0;
+        let base_radius = self.config.base_radius;
+        if fraction <= 0.0 {
+            return;
+        }
+
+        let mut carry = std::mem::take(&mut self.decay_carry);
+        for player in self.players.values_mut() {
+            let mut lost = 0;
+            for cell in player.cells.iter_mut() {
+                if cell.score > threshold {
+                    let cell_carry = carry.entry(cell.id).or_insert(0.0);
+                    lost += decay_cell(cell, threshold, fraction, cell_carry, base_radius);
+                }
+            }
+            if lost > 0 {
+                refresh_player(player, base_radius);
+            }
+        }
+        // Forget cells that were eaten or merged
+        carry.retain(|id, _| self.cell_owner.contains_key(id));
+        self.decay_carry = carry;
+    }
+
+    /// Top players by score (ties broken by id so the order is stable)
+    pub fn leaderboard(&self, count: usize) -> Vec<LeaderboardEntry> {
+        let mut ranked: Vec<&PlayerSpec> = self.players.values().collect();
+        ranked.sort_by(|a, b| b.score.cmp(&a.score).then(a.id.cmp(&b.id)));
+        ranked
+            .into_iter()
+            .take(count)
+            .map(|p| LeaderboardEntry { id: p.id, name: p.name.clone(), score: p.score, team: p.team, bot: p.bot })
+            .collect()
+    }
+
+    /// Every team's combined score, best first (empty in free-for-all)
+    pub fn team_scores(&self) -> Vec<TeamScore> {
+        let mut teams: Vec<TeamScore> = (0..self.config.team_count)
+            .map(|team| TeamScore { team: team as u8, score: 0, players: 0 })
+            .collect();
+        for p in self.players.values() {
+            if let Some(entry) = p.team.and_then(|team| teams.get_mut(team as usize)) {
+                entry.score += p.score;
+                entry.players += 1;
+            }
+        }
+        teams.sort_by(|a, b| b.score.cmp(&a.score).then(a.team.cmp(&b.team)));
+        teams
+    }
+
     /// Convert current world into snapshot
     pub fn to_snapshot(&self) -> GameSnapshot {
         GameSnapshot {
@@ -389,7 +1145,288 @@ impl GameState {
             status: self.status,  // Include game status
             players: self.players.values().cloned().collect(),
             dots: self.dots.values().cloned().collect(),
+            viruses: self.viruses.values().cloned().collect(),
+            leaderboard: self.leaderboard(self.config.leaderboard_size),
+            teams: self.team_scores(),
+            lobby: self.lobby_state(),
+            round: self.round.clone(),
             constants: self.constants.clone(),
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> GameState {
+        GameState::new(GameConfig { initial_dot_count: 0, virus_count: 0, ..GameConfig::default() })
+    }
+
+    #[test]
+    fn parked_player_resumes_with_its_state() {
+        let mut gs = world();
+        gs.add_player(1);
+        gs.handle_message(1, ClientMessage::Ready);
+        gs.players.get_mut(&1).unwrap().score = 42;
+        let token = gs.issue_resume_token(1);
+
+        assert!(gs.park_player(1, 100));
+        assert!(!gs.players.contains_key(&1));
+        assert!(gs.player_grid.is_empty());
+        assert!(!gs.park_player(1, 100));
+
+        assert_eq!(gs.resume_player(&token), Some(1));
+        assert_eq!(gs.players[&1].score, 42);
+        assert_eq!(gs.ready_players.get(&1), Some(&true));
+        assert!(!gs.player_grid.is_empty());
+        // The token stays valid for the next reconnect
+        assert_eq!(gs.resume_player(&token), Some(1));
+    }
+
+    #[test]
+    fn resume_tokens_are_per_player() {
+        let mut gs = world();
+        gs.add_player(1);
+        gs.add_player(2);
+        let first = gs.issue_resume_token(1);
+        let second = gs.issue_resume_token(2);
+        assert_ne!(first, second);
+
+        gs.park_player(2, 100);
+        assert_eq!(gs.resume_player(&second), Some(2));
+        assert_eq!(gs.resume_player("not-a-token"), None);
+    }
+
+    #[test]
+    fn parked_player_expires_after_the_grace_period() {
+        let mut gs = world();
+        gs.add_player(1);
+        let token = gs.issue_resume_token(1);
+        gs.park_player(1, 10);
+
+        gs.tick += 9;
+        gs.expire_parked_players();
+        assert!(gs.parked.contains_key(&1));
+
+        gs.tick += 1;
+        gs.expire_parked_players();
+        assert!(gs.parked.is_empty());
+        assert_eq!(gs.resume_player(&token), None);
+    }
+
+    #[test]
+    fn removed_player_cannot_be_resumed() {
+        let mut gs = world();
+        gs.add_player(1);
+        let token = gs.issue_resume_token(1);
+        gs.remove_player(1);
+        assert_eq!(gs.resume_player(&token), None);
+        assert!(gs.resume_tokens.is_empty());
+    }
+
+    #[test]
+    fn moves_are_acknowledged_once_they_start() {
+        let mut gs = GameState::new(GameConfig {
+            initial_dot_count: 0,
+            virus_count: 0,
+            movement_mode: MovementMode::Discrete,
+            ..GameConfig::default()
+        });
+        gs.add_player(1);
+        let step = |sequence_number| ClientMessage::Move { dx: 1.0, dy: 0.0, distance: 10.0, sequence_number };
+        gs.handle_message(1, step(1));
+        gs.apply_pending_moves();
+        assert_eq!(gs.players[&1].sequence_number, 1);
+
+        // Sent while the first step is still running: it waits, unacknowledged
+        gs.handle_message(1, step(2));
+        gs.apply_pending_moves();
+        assert_eq!(gs.players[&1].sequence_number, 1);
+
+        gs.players.get_mut(&1).unwrap().remaining_distance = 0.0;
+        gs.apply_pending_moves();
+        assert_eq!(gs.players[&1].sequence_number, 2);
+        assert_eq!(gs.players[&1].remaining_distance, 10.0);
+    }
+
+    #[test]
+    fn removed_bots_stop_counting() {
+        let mut gs = GameState::new(GameConfig { initial_dot_count: 0, virus_count: 0, bot_count: 3, ..GameConfig::default() });
+        gs.add_player(1);
+        gs.update_bots();
+        assert_eq!(gs.bot_count(), 2);
+        assert_eq!(gs.player_count(), 1);
+
+        for bot in gs.bots.clone() {
+            gs.remove_player(bot);
+        }
+        assert_eq!(gs.bot_count(), 0);
+        assert_eq!(gs.player_count(), 1);
+        assert_eq!(gs.connected_human_count(), 1);
+        gs.update_bots();
+        assert_eq!(gs.bot_count(), 2);
+    }
+
+    /// Players 1 and 2 in a room that needs both of them, with a 4 tick countdown
+    fn lobby() -> GameState {
+        let mut gs = GameState::new(GameConfig {
+            initial_dot_count: 0,
+            virus_count: 0,
+            lobby_min_players: 2,
+            lobby_countdown_ms: 200,
+            ..GameConfig::default()
+        });
+        gs.add_player(1);
+        gs.add_player(2);
+        gs
+    }
+
+    #[test]
+    fn countdown_starts_once_enough_players_are_ready() {
+        let mut gs = lobby();
+        gs.handle_message(1, ClientMessage::Ready);
+        gs.update_lobby();
+        assert_eq!(gs.lobby_state().countdown_ms, None);
+
+        gs.handle_message(2, ClientMessage::Ready);
+        gs.update_lobby();
+        assert_eq!(gs.lobby_state().countdown_ms, Some(200));
+
+        gs.tick += 3;
+        gs.update_lobby();
+        assert_eq!(gs.lobby_state().countdown_ms, Some(50));
+        assert_eq!(gs.status, GameStatus::WaitingToStart);
+
+        gs.tick += 1;
+        gs.update_lobby();
+        assert_eq!(gs.status, GameStatus::Playing);
+        assert_eq!(gs.lobby_state().countdown_ms, None);
+    }
+
+    #[test]
+    fn countdown_stops_when_a_player_is_no_longer_ready() {
+        let mut gs = lobby();
+        gs.handle_message(1, ClientMessage::Ready);
+        gs.handle_message(2, ClientMessage::Ready);
+        gs.update_lobby();
+        gs.handle_message(2, ClientMessage::Unready);
+        gs.update_lobby();
+        assert_eq!(gs.lobby_state().countdown_ms, None);
+
+        // A newcomer who is not ready yet holds the start back too
+        gs.handle_message(2, ClientMessage::Ready);
+        gs.update_lobby();
+        gs.add_player(3);
+        gs.update_lobby();
+        assert_eq!(gs.lobby_state().countdown_ms, None);
+
+        gs.tick += 10;
+        gs.update_lobby();
+        assert_eq!(gs.status, GameStatus::WaitingToStart);
+    }
+
+    #[test]
+    fn roster_lists_everyone_in_join_order() {
+        let mut gs = lobby();
+        gs.handle_message(2, ClientMessage::Ready);
+        let roster = gs.lobby_state().roster;
+        assert_eq!(roster.iter().map(|e| (e.id, e.ready)).collect::<Vec<_>>(), vec![(1, false), (2, true)]);
+        assert_eq!(gs.lobby_state().min_players, 2);
+    }
+
+    #[test]
+    fn round_ends_on_time_and_the_world_resets_after_the_results() {
+        let mut gs = GameState::new(GameConfig {
+            initial_dot_count: 0,
+            virus_count: 0,
+            lobby_min_players: 1,
+            lobby_countdown_ms: 0,
+            round_duration_ms: 1000,
+            results_duration_ms: 500,
+            ..GameConfig::default()
+        });
+        gs.add_player(1);
+        gs.add_player(2);
+        gs.handle_message(1, ClientMessage::Ready);
+        gs.handle_message(2, ClientMessage::Ready);
+        gs.update_lobby();
+        gs.update_lobby();
+        assert_eq!(gs.status, GameStatus::Playing);
+        assert_eq!(gs.round.number, 1);
+        assert_eq!(gs.round.ends_at_tick, Some(gs.tick + 1000 / gs.constants.tick_interval_ms));
+
+        gs.players.get_mut(&2).unwrap().score = 50;
+        gs.tick = gs.round.ends_at_tick.unwrap();
+        gs.update_round();
+        assert_eq!(gs.status, GameStatus::GameOver);
+        assert_eq!(gs.round.results.iter().map(|r| (r.rank, r.id)).collect::<Vec<_>>(), vec![(1, 2), (2, 1)]);
+        assert_eq!(gs.round.results[0].peak_score, 50);
+
+        gs.tick = gs.round.next_round_at_tick.unwrap();
+        gs.update_round();
+        assert_eq!(gs.status, GameStatus::WaitingToStart);
+        assert!(gs.round.results.is_empty());
+        assert_eq!(gs.round.next_round_at_tick, None);
+    }
+
+    fn teams(team_count: usize) -> GameState {
+        GameState::new(GameConfig { initial_dot_count: 0, virus_count: 0, team_count, ..GameConfig::default() })
+    }
+
+    #[test]
+    fn new_players_join_the_smallest_team() {
+        let mut gs = teams(3);
+        for id in 1..=4 {
+            gs.add_player(id);
+        }
+        let team_of = |gs: &GameState, id| gs.players[&id].team;
+        assert_eq!((1..=4).map(|id| team_of(&gs, id)).collect::<Vec<_>>(), vec![Some(0), Some(1), Some(2), Some(0)]);
+
+        // Parked players still hold their place on the team
+        gs.park_player(2, 100);
+        gs.add_player(5);
+        assert_eq!(team_of(&gs, 5), Some(1));
+
+        let free_for_all = {
+            let mut gs = teams(0);
+            gs.add_player(1);
+            gs
+        };
+        assert_eq!(team_of(&free_for_all, 1), None);
+    }
+
+    #[test]
+    fn players_choose_teams_outside_a_round_only() {
+        let mut gs = teams(2);
+        gs.add_player(1);
+        gs.add_player(2);
+        assert!(!gs.same_team(1, 2));
+
+        gs.handle_message(2, ClientMessage::ChooseTeam { team: 0 });
+        assert!(gs.same_team(1, 2));
+        gs.handle_message(2, ClientMessage::ChooseTeam { team: 2 });
+        assert_eq!(gs.players[&2].team, Some(0));
+
+        gs.status = GameStatus::Playing;
+        gs.handle_message(2, ClientMessage::ChooseTeam { team: 1 });
+        assert_eq!(gs.players[&2].team, Some(0));
+
+        let scores = gs.team_scores();
+        assert_eq!(scores.iter().map(|t| (t.team, t.players)).collect::<Vec<_>>(), vec![(0, 2), (1, 0)]);
+        assert!(!teams(0).same_team(1, 2));
+    }
+
+    #[test]
+    fn teams_beyond_the_team_count_are_ignored_when_balancing() {
+        let mut gs = teams(2);
+        gs.add_player(1);
+        // Kept from a run with more teams
+        gs.players.get_mut(&1).unwrap().team = Some(5);
+        gs.add_player(2);
+        gs.add_player(3);
+        assert_eq!(gs.players[&2].team, Some(0));
+        assert_eq!(gs.players[&3].team, Some(1));
+    }
+}
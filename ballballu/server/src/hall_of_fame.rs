@@ -0,0 +1,157 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use rusqlite::{params, Connection};
+use tracing::error;
+
+use shared::protocol::{HallOfFameEntry, HallOfFameMessage};
+
+/// One finished life of a human player, written to the database
+#[derive(Debug, Clone)]
+pub struct LifeRecord {
+    pub name: String,
+    pub peak_score: u32,
+    pub players_eaten: u32,
+    pub dots_eaten: u32,
+    pub survival_ms: u64,
+    pub ended_at: u64, // unix time (seconds)
+}
+
+/// All-time leaderboard of single lives, kept in an SQLite file
+/// Cheap to clone; SQLite calls run on the blocking thread pool so they
+/// never stall a tick task.
+#[derive(Clone)]
+pub struct HallOfFame {
+    conn: Arc<Mutex<Connection>>,
+    top_n: usize,
+}
+
+impl HallOfFame {
+    /// Open (or create) the database file
+    pub fn open(path: &Path, top_n: usize) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS lives (
+                id            INTEGER PRIMARY KEY,
+                name          TEXT    NOT NULL,
+                peak_score    INTEGER NOT NULL,
+                players_eaten INTEGER NOT NULL,
+                dots_eaten    INTEGER NOT NULL,
+                survival_ms   INTEGER NOT NULL,
+                ended_at      INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS lives_by_score ON lives (peak_score DESC);
+            CREATE INDEX IF NOT EXISTS lives_by_end ON lives (ended_at);",
+        )?;
+        Ok(Self { conn: Arc::new(Mutex::new(conn)), top_n })
+    }
+
+    /// Store finished lives in one transaction
+    pub async fn record(&self, lives: Vec<LifeRecord>) {
+        let conn = self.conn.clone();
+        let result = tokio::task::spawn_blocking(move || -> rusqlite::Result<()> {
+            let mut conn = conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            {
+                let mut insert = tx.prepare_cached(
+                    "INSERT INTO lives (name, peak_score, players_eaten, dots_eaten, survival_ms, ended_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for life in &lives {
+                    insert.execute(params![
+                        life.name,
+                        life.peak_score,
+                        life.players_eaten,
+                        life.dots_eaten,
+                        life.survival_ms,
+                        life.ended_at,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Hall of fame: cannot record lives: {}", e),
+            Err(e) => error!("Hall of fame: record task failed: {}", e),
+        }
+    }
+
+    /// Top lives of all time and of today (UTC)
+    pub async fn top(&self) -> HallOfFameMessage {
+        let conn = self.conn.clone();
+        let top_n = self.top_n;
+        let result = tokio::task::spawn_blocking(move || -> rusqlite::Result<HallOfFameMessage> {
+            let conn = conn.lock().unwrap();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let midnight = now - now % 86_400;
+            Ok(HallOfFameMessage {
+                all_time: best_since(&conn, 0, top_n)?,
+                daily: best_since(&conn, midnight, top_n)?,
+            })
+        })
+        .await;
+        match result {
+            Ok(Ok(message)) => message,
+            Ok(Err(e)) => {
+                error!("Hall of fame: cannot read leaderboard: {}", e);
+                HallOfFameMessage::default()
+            }
+            Err(e) => {
+                error!("Hall of fame: read task failed: {}", e);
+                HallOfFameMessage::default()
+            }
+        }
+    }
+}
+
+/// Best `limit` lives that ended at or after `since` (unix seconds)
+fn best_since(conn: &Connection, since: u64, limit: usize) -> rusqlite::Result<Vec<HallOfFameEntry>> {
+    let mut query = conn.prepare_cached(
+        "SELECT name, peak_score, players_eaten, dots_eaten, survival_ms, ended_at
+         FROM lives WHERE ended_at >= ?1
+         ORDER BY peak_score DESC, survival_ms DESC, id ASC
+         LIMIT ?2",
+    )?;
+    let rows = query.query_map(params![since, limit as u64], |row| {
+        Ok(HallOfFameEntry {
+            name: row.get(0)?,
+            peak_score: row.get(1)?,
+            players_eaten: row.get(2)?,
+            dots_eaten: row.get(3)?,
+            survival_ms: row.get(4)?,
+            ended_at: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(name: &str, peak_score: u32, ended_at: u64) -> LifeRecord {
+        LifeRecord { name: name.to_string(), peak_score, players_eaten: 1, dots_eaten: 2, survival_ms: 3000, ended_at }
+    }
+
+    #[tokio::test]
+    async fn lives_survive_reopening_the_database() {
+        let path = std::env::temp_dir().join(format!("hall_of_fame_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        let hall = HallOfFame::open(&path, 2).unwrap();
+        hall.record(vec![life("old", 900, 1000), life("small", 10, now)]).await;
+        hall.record(vec![life("today", 500, now)]).await;
+        drop(hall);
+
+        let top = HallOfFame::open(&path, 2).unwrap().top().await;
+        let names = |entries: &[HallOfFameEntry]| entries.iter().map(|e| e.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&top.all_time), vec!["old", "today"]);
+        assert_eq!(names(&top.daily), vec!["today", "small"]);
+        assert_eq!(top.all_time[0].survival_ms, 3000);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,50 +1,80 @@
 use std::convert::Infallible;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 use hyper::body::Bytes;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
-use hyper::{Request, Response, StatusCode};
+use http_body_util::{BodyExt, Limited};
+use hyper::{Method, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
 use tokio::net::TcpListener;
 use tokio::fs;
+use tracing::{debug, info, warn};
+
+use crate::admin::{self, Admin};
+use crate::api::{self, ApiState};
+use crate::hall_of_fame::HallOfFame;
+use crate::metrics;
+use crate::room_manager::RoomManager;
+
+// Longest admin command accepted over HTTP
+const ADMIN_BODY_LIMIT: usize = 4096;
 
 /// Simple HTTP server to serve static files
+/// Paths below `/api/` are answered by the read-only JSON API (see `api`),
+/// `/metrics` by the Prometheus exporter (see `metrics`), `POST /admin` by
+/// the admin console (see `admin`).
 pub struct HttpServer {
     pub addr: String,
     pub static_dir: PathBuf,
+    pub api: ApiState,
+    pub admin: Admin,
 }
 
 impl HttpServer {
-    pub fn new(addr: &str, static_dir: PathBuf) -> Self {
+    pub fn new(
+        addr: &str,
+        static_dir: PathBuf,
+        rooms: Arc<RoomManager>,
+        hall_of_fame: Option<HallOfFame>,
+        admin: Admin,
+    ) -> Self {
         Self {
             addr: addr.to_string(),
             static_dir,
+            api: ApiState { rooms, hall_of_fame, started_at: Instant::now() },
+            admin,
         }
     }
 
     pub async fn run(&self) {
         let listener = TcpListener::bind(&self.addr).await.unwrap();
-        println!("HTTP server running at http://{}/", self.addr);
+        info!("HTTP server running at http://{}/", self.addr);
 
         loop {
             match listener.accept().await {
                 Ok((stream, _)) => {
                     let io = TokioIo::new(stream);
                     let static_dir = self.static_dir.clone();
+                    let api = self.api.clone();
+                    let admin = self.admin.clone();
 
                     tokio::spawn(async move {
-                        let service = service_fn(move |req| handle_request(req, static_dir.clone()));
+                        let service = service_fn(move |req| {
+                            handle_request(req, static_dir.clone(), api.clone(), admin.clone())
+                        });
 
                         if let Err(err) = http1::Builder::new()
                             .serve_connection(io, service)
                             .await
                         {
-                            eprintln!("Error serving connection: {:?}", err);
+                            debug!("Error serving connection: {:?}", err);
                         }
                     });
                 }
                 Err(e) => {
-                    eprintln!("Error accepting connection: {:?}", e);
+                    warn!("Error accepting connection: {:?}", e);
                 }
             }
         }
@@ -54,11 +84,29 @@ impl HttpServer {
 async fn handle_request(
     req: Request<hyper::body::Incoming>,
     static_dir: PathBuf,
+    api: ApiState,
+    admin: Admin,
 ) -> Result<Response<http_body_util::Full<Bytes>>, Infallible> {
     let path = req.uri().path();
 
+    if path == "/admin" {
+        return Ok(handle_admin(req, &admin).await);
+    }
+
+    if path == "/api" || path.starts_with("/api/") {
+        return Ok(api::handle(req.method(), path, req.uri().query(), &api).await);
+    }
+
+    if path == "/metrics" {
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(http_body_util::Full::new(Bytes::from(metrics::get().render())))
+            .unwrap());
+    }
+
     // Default to index.html if root path
-    let file_path = if path == "/" || path == "" {
+    let file_path = if path == "/" || path.is_empty() {
         static_dir.join("test.html")
     } else {
         // Remove leading slash
@@ -115,7 +163,32 @@ async fn handle_request(
     }
 }
 
-fn get_content_type(path: &PathBuf) -> &str {
+/// Run the admin command in the request body, the reply is plain text
+/// Needs `Authorization: Bearer <[admin] token>`.
+async fn handle_admin(req: Request<hyper::body::Incoming>, admin: &Admin) -> Response<http_body_util::Full<Bytes>> {
+    let text = |status: StatusCode, body: String| {
+        Response::builder()
+            .status(status)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(http_body_util::Full::new(Bytes::from(body)))
+            .unwrap()
+    };
+    if req.method() != Method::POST {
+        return text(StatusCode::METHOD_NOT_ALLOWED, "POST a command\n".to_string());
+    }
+    let header = req.headers().get("authorization").and_then(|v| v.to_str().ok());
+    if !admin.authorize(header.and_then(admin::bearer_token)) {
+        return text(StatusCode::UNAUTHORIZED, "admin token required\n".to_string());
+    }
+    let command = match Limited::new(req.into_body(), ADMIN_BODY_LIMIT).collect().await {
+        Ok(body) => String::from_utf8_lossy(&body.to_bytes()).into_owned(),
+        Err(_) => return text(StatusCode::PAYLOAD_TOO_LARGE, "command too long\n".to_string()),
+    };
+    text(StatusCode::OK, admin.execute(&command).await + "\n")
+}
+
+
+fn get_content_type(path: &Path) -> &str {
     match path.extension().and_then(|s| s.to_str()) {
         Some("html") => "text/html; charset=utf-8",
         Some("css") => "text/css; charset=utf-8",
@@ -0,0 +1,125 @@
+use shared::GameSnapshot;
+use crate::config::InterestConfig;
+
+/// Interest management
+/// Cut a full snapshot down to what one player can see: entities within the
+/// view distance of their position. A player is visible if any of its
+/// cells is. The leaderboard summary stays global.
+/// Unknown viewers get no entities at all (never leak the whole world).
+pub fn visible_snapshot(full: &GameSnapshot, viewer_id: u64, config: &InterestConfig) -> GameSnapshot {
+    let (cx, cy, view) = match full.players.iter().find(|p| p.id == viewer_id) {
+        Some(me) => (me.x, me.y, config.view_radius + config.size_scale * me.radius),
+        None => (0.0, 0.0, -1.0),
+    };
+
+    // Entity is visible if any part of its circle is inside the view circle
+    let in_view = |x: f32, y: f32, radius: f32| {
+        let reach = view + radius;
+        reach >= 0.0 && (x - cx).powi(2) + (y - cy).powi(2) <= reach * reach
+    };
+
+    GameSnapshot {
+        tick: full.tick,
+        status: full.status,
+        players: full
+            .players
+            .iter()
+            .filter(|p| p.id == viewer_id || p.cells.iter().any(|c| in_view(c.x, c.y, c.radius)))
+            .cloned()
+            .collect(),
+        dots: full
+            .dots
+            .iter()
+            .filter(|d| in_view(d.x, d.y, d.radius))
+            .cloned()
+            .collect(),
+        viruses: full
+            .viruses
+            .iter()
+            .filter(|v| in_view(v.x, v.y, v.radius))
+            .cloned()
+            .collect(),
+        leaderboard: full.leaderboard.clone(),
+        teams: full.teams.clone(),
+        lobby: full.lobby.clone(),
+        round: full.round.clone(),
+        constants: full.constants.clone(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+    use crate::game_state::GameState;
+
+    const CONFIG: InterestConfig = InterestConfig { enabled: true, view_radius: 100.0, size_scale: 0.0 };
+
+    /// Players 1 (the viewer), 2 (at the edge of its view) and 3 (out of
+    /// view), a dot and a virus next to the viewer and one of each far away
+    fn world() -> GameSnapshot {
+        let mut gs = GameState::new(GameConfig { initial_dot_count: 0, virus_count: 0, ..GameConfig::default() });
+        for id in 1..=3 {
+            gs.add_player(id);
+        }
+        gs.spawn_new_dot();
+        gs.spawn_new_dot();
+        gs.add_virus(450.0, 500.0, 0.0, 0.0);
+        gs.add_virus(500.0, 900.0, 0.0, 0.0);
+        let mut snapshot = gs.to_snapshot();
+        snapshot.players.sort_by_key(|p| p.id);
+        for (p, x) in snapshot.players.iter_mut().zip([500.0, 605.0, 800.0]) {
+            p.x = x;
+            p.y = 500.0;
+            for cell in p.cells.iter_mut() {
+                cell.x = x;
+                cell.y = 500.0;
+            }
+        }
+        for (d, x) in snapshot.dots.iter_mut().zip([550.0, 900.0]) {
+            d.x = x;
+            d.y = 500.0;
+        }
+        snapshot
+    }
+
+    fn player_ids(snapshot: &GameSnapshot) -> Vec<u64> {
+        let mut ids: Vec<u64> = snapshot.players.iter().map(|p| p.id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn keeps_what_overlaps_the_view_circle() {
+        let full = world();
+        let view = visible_snapshot(&full, 1, &CONFIG);
+        assert_eq!(player_ids(&view), vec![1, 2]);
+        assert_eq!(view.dots.len(), 1);
+        assert_eq!(view.dots[0].x, 550.0);
+        assert_eq!(view.viruses.len(), 1);
+        assert_eq!(view.viruses[0].x, 450.0);
+        assert_eq!(view.leaderboard, full.leaderboard);
+        assert_eq!(view.tick, full.tick);
+    }
+
+    #[test]
+    fn bigger_players_see_further() {
+        let full = world();
+        let config = InterestConfig { size_scale: 40.0, ..CONFIG };
+        let view = visible_snapshot(&full, 1, &config);
+        assert_eq!(player_ids(&view), vec![1, 2, 3]);
+        assert_eq!(view.dots.len(), 2);
+        assert_eq!(view.viruses.len(), 2);
+    }
+
+    #[test]
+    fn unknown_viewers_see_nothing_but_the_leaderboard() {
+        let full = world();
+        let view = visible_snapshot(&full, 99, &CONFIG);
+        assert!(view.players.is_empty());
+        assert!(view.dots.is_empty());
+        assert!(view.viruses.is_empty());
+        assert_eq!(view.leaderboard.len(), 3);
+    }
+}
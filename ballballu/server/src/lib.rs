@@ -0,0 +1,13 @@
+pub mod config;
+pub mod websocket_manager;
+pub mod game_state;
+pub mod game_loop;
+pub mod room_manager;
+pub mod http_server;
+pub mod api;
+pub mod metrics;
+pub mod logging;
+pub mod interest;
+pub mod bots;
+pub mod hall_of_fame;
+pub mod admin;
@@ -0,0 +1,9 @@
+use shared::logging::WorkerGuard;
+
+use crate::config::LoggingConfig;
+
+/// Install the global `tracing` subscriber from the `[logging]` section
+/// See `shared::logging::init`; keep the returned guard alive until exit.
+pub fn init(config: &LoggingConfig) -> Result<Option<WorkerGuard>, String> {
+    shared::logging::init(&config.filter, config.json_file.as_deref())
+}
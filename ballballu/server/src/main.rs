@@ -1,29 +1,59 @@
-mod websocket_manager;
-mod game_state;
-mod game_loop;
-mod http_server;
-
 use std::sync::Arc;
 use std::path::PathBuf;
-use websocket_manager::WebSocketManager;
-use game_loop::GameLoop;
-use http_server::HttpServer;
+use clap::Parser;
+use server::config::{Cli, ServerConfig};
+use server::websocket_manager::WebSocketManager;
+use server::http_server::HttpServer;
+use server::hall_of_fame::HallOfFame;
+use server::logging;
+use server::admin;
 
 #[tokio::main]
 async fn main() {
-    // WebSocket server for game communication
-    let ws = Arc::new(WebSocketManager::new("0.0.0.0:34568").await);
-    let game_loop = GameLoop::new(ws.clone());
+    // Load config file + command line overrides, refuse to start on bad values
+    let cli = Cli::parse();
+    let config = match ServerConfig::load(&cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Log output (the guard flushes the JSON log file on exit)
+    let _log_guard = match logging::init(&config.logging) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // All-time leaderboard database
+    let hall_of_fame = if config.hall_of_fame.enabled {
+        let path = &config.hall_of_fame.db_path;
+        match HallOfFame::open(path, config.hall_of_fame.top_n) {
+            Ok(hall_of_fame) => Some(hall_of_fame),
+            Err(e) => {
+                tracing::error!("Cannot open hall of fame database {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
 
-    // HTTP server for static files (test.html, styles.css, app.js)
+    // WebSocket server for game communication (opens the rooms and starts their game loops)
+    let ws = Arc::new(WebSocketManager::new(&config, hall_of_fame.clone()).await);
+
+    // HTTP server for static files (test.html, styles.css, app.js) and the JSON API
     let static_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("static");
-    let http_server = HttpServer::new("0.0.0.0:34567", static_dir);
+    let http_server = HttpServer::new(&config.network.http_addr, static_dir, ws.rooms.clone(), hall_of_fame, ws.admin.clone());
 
-    // Spawn WebSocket accept loop
-    let ws_clone = ws.clone();
-    tokio::spawn(async move {
-        ws_clone.run_accept_loop().await;
-    });
+    // Admin commands typed into the terminal
+    if config.admin.console {
+        tokio::spawn(admin::run_console(ws.admin.clone()));
+    }
 
     // Spawn HTTP server for static files
     let http_server_clone = http_server;
@@ -31,6 +61,6 @@ async fn main() {
         http_server_clone.run().await;
     });
 
-    // Run game loop (blocks here)
-    game_loop.run().await;
+    // Run WebSocket accept loop (blocks here)
+    ws.run_accept_loop().await;
 }
@@ -0,0 +1,195 @@
+use std::sync::LazyLock;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use shared::protocol::{ClientMessage, ServerMessage};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Server health counters, exported at `/metrics` in the Prometheus text format
+/// Room metrics are labelled with the room name and dropped when the room closes.
+pub struct Metrics {
+    registry: Registry,
+    pub tick_duration: HistogramVec,       // room; seconds of tick work, broadcast included
+    pub tick_overruns: IntCounterVec,      // room; ticks that took longer than the tick interval
+    pub bytes_broadcast: HistogramVec,     // room; snapshot bytes queued to all clients in one tick
+    pub collisions: IntCounterVec,         // room, kind (dot / cell / virus)
+    pub room_players: IntGaugeVec,         // room; humans, parked ones included
+    pub room_bots: IntGaugeVec,            // room
+    pub room_dots: IntGaugeVec,            // room; dots and ejected pellets in the world
+    pub connections: IntGauge,             // open WebSocket connections
+    pub messages_received: IntCounterVec,  // type; `invalid` for undecodable messages
+    pub messages_sent: IntCounterVec,      // type
+}
+
+/// The process-wide metrics
+pub fn get() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("ballballu".to_string()), None)
+            .expect("valid metrics prefix");
+        let metrics = Self {
+            tick_duration: HistogramVec::new(
+                HistogramOpts::new("tick_duration_seconds", "Time spent on one game tick, broadcast included")
+                    .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25]),
+                &["room"],
+            )
+            .unwrap(),
+            tick_overruns: IntCounterVec::new(
+                Opts::new("tick_overruns_total", "Ticks that took longer than the tick interval"),
+                &["room"],
+            )
+            .unwrap(),
+            bytes_broadcast: HistogramVec::new(
+                HistogramOpts::new("broadcast_bytes", "Snapshot bytes sent to all clients in one tick")
+                    .buckets(prometheus::exponential_buckets(1024.0, 4.0, 8).unwrap()),
+                &["room"],
+            )
+            .unwrap(),
+            collisions: IntCounterVec::new(
+                Opts::new("collisions_total", "Collisions processed (dots eaten, cells eaten, virus pops)"),
+                &["room", "kind"],
+            )
+            .unwrap(),
+            room_players: IntGaugeVec::new(Opts::new("room_players", "Human players in a room"), &["room"])
+                .unwrap(),
+            room_bots: IntGaugeVec::new(Opts::new("room_bots", "Bots in a room"), &["room"]).unwrap(),
+            room_dots: IntGaugeVec::new(Opts::new("room_dots", "Dots in a room's world"), &["room"]).unwrap(),
+            connections: IntGauge::new("connections", "Open WebSocket connections").unwrap(),
+            messages_received: IntCounterVec::new(
+                Opts::new("messages_received_total", "Client messages received"),
+                &["type"],
+            )
+            .unwrap(),
+            messages_sent: IntCounterVec::new(
+                Opts::new("messages_sent_total", "Server messages sent"),
+                &["type"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 10] = [
+            Box::new(metrics.tick_duration.clone()),
+            Box::new(metrics.tick_overruns.clone()),
+            Box::new(metrics.bytes_broadcast.clone()),
+            Box::new(metrics.collisions.clone()),
+            Box::new(metrics.room_players.clone()),
+            Box::new(metrics.room_bots.clone()),
+            Box::new(metrics.room_dots.clone()),
+            Box::new(metrics.connections.clone()),
+            Box::new(metrics.messages_received.clone()),
+            Box::new(metrics.messages_sent.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("metric names are unique");
+        }
+        metrics
+    }
+
+    /// Everything in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Metrics: cannot encode: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Count one decoded client message, or an undecodable one
+    pub fn message_received(&self, msg: Option<&ClientMessage>) {
+        let kind = msg.map_or("invalid", client_message_kind);
+        self.messages_received.with_label_values(&[kind]).inc();
+    }
+
+    /// Count one server message queued to a client
+    pub fn message_sent(&self, msg: &ServerMessage) {
+        self.messages_sent.with_label_values(&[server_message_kind(msg)]).inc();
+    }
+
+    /// Drop the series of a room that closed
+    pub fn forget_room(&self, room: &str) {
+        let _ = self.tick_duration.remove_label_values(&[room]);
+        let _ = self.tick_overruns.remove_label_values(&[room]);
+        let _ = self.bytes_broadcast.remove_label_values(&[room]);
+        for kind in ["dot", "cell", "virus"] {
+            let _ = self.collisions.remove_label_values(&[room, kind]);
+        }
+        let _ = self.room_players.remove_label_values(&[room]);
+        let _ = self.room_bots.remove_label_values(&[room]);
+        let _ = self.room_dots.remove_label_values(&[room]);
+    }
+}
+
+fn client_message_kind(msg: &ClientMessage) -> &'static str {
+    match msg {
+        ClientMessage::Join { .. } => "join",
+        ClientMessage::Input { .. } => "input",
+        ClientMessage::Move { .. } => "move",
+        ClientMessage::Split => "split",
+        ClientMessage::EjectMass => "eject_mass",
+        ClientMessage::Ready => "ready",
+        ClientMessage::Unready => "unready",
+        ClientMessage::ChooseTeam { .. } => "choose_team",
+        ClientMessage::Ack { .. } => "ack",
+        ClientMessage::Resume { .. } => "resume",
+        ClientMessage::ListRooms => "list_rooms",
+        ClientMessage::JoinRoom { .. } => "join_room",
+        ClientMessage::HallOfFame => "hall_of_fame",
+        ClientMessage::Quit => "quit",
+    }
+}
+
+fn server_message_kind(msg: &ServerMessage) -> &'static str {
+    match msg {
+        ServerMessage::Welcome(_) => "welcome",
+        ServerMessage::StateUpdate(_) => "state_update",
+        ServerMessage::DeltaUpdate(_) => "delta_update",
+        ServerMessage::RoomList(_) => "room_list",
+        ServerMessage::HallOfFame(_) => "hall_of_fame",
+        ServerMessage::Bye(_) => "bye",
+        ServerMessage::Announcement(_) => "announcement",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::protocol::ByeMessage;
+
+    #[test]
+    fn messages_are_counted_by_type() {
+        let metrics = Metrics::new();
+        metrics.message_received(Some(&ClientMessage::Split));
+        metrics.message_received(Some(&ClientMessage::Split));
+        metrics.message_received(None);
+        metrics.message_sent(&ServerMessage::Bye(ByeMessage { reason: "bye".to_string() }));
+
+        assert_eq!(metrics.messages_received.with_label_values(&["split"]).get(), 2);
+        assert_eq!(metrics.messages_received.with_label_values(&["invalid"]).get(), 1);
+        let text = metrics.render();
+        assert!(text.contains("ballballu_messages_received_total{type=\"split\"} 2"));
+        assert!(text.contains("ballballu_messages_sent_total{type=\"bye\"} 1"));
+    }
+
+    #[test]
+    fn closed_rooms_are_forgotten() {
+        let metrics = Metrics::new();
+        for room in ["lobby", "duel"] {
+            metrics.room_players.with_label_values(&[room]).set(3);
+            metrics.collisions.with_label_values(&[room, "virus"]).inc();
+        }
+        metrics.forget_room("duel");
+
+        let text = metrics.render();
+        assert!(text.contains("ballballu_room_players{room=\"lobby\"} 3"));
+        assert!(text.contains("ballballu_collisions_total{kind=\"virus\",room=\"lobby\"} 1"));
+        assert!(!text.contains("room=\"duel\""));
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, info_span, Instrument};
+
+use shared::GameStatus;
+use shared::protocol::RoomInfo;
+use crate::admin::BanList;
+use crate::config::{GameConfig, InterestConfig, ServerConfig};
+use crate::game_loop::GameLoop;
+use crate::game_state::GameState;
+use crate::hall_of_fame::HallOfFame;
+use crate::interest;
+use crate::metrics;
+use crate::websocket_manager::Connection;
+
+/// Longest room name a client may ask for (`ClientMessage::JoinRoom`)
+pub const MAX_ROOM_NAME_CHARS: usize = 32;
+
+/// One arena: an independent world with its own tick task and clients
+pub struct Room {
+    pub id: u64,
+    pub name: String,
+    pub max_players: usize,
+    pub on_demand: bool, // opened because the others were full; closed once empty
+    pub game_state: Arc<Mutex<GameState>>,
+    // Phase 3: Connections for broadcasting
+    pub connections: Arc<Mutex<HashMap<u64, Connection>>>,
+    delta_updates: bool,
+    keyframe_interval: u64,
+    interest: InterestConfig,
+    pub hall_of_fame: Option<HallOfFame>, // where finished lives are recorded
+}
+
+impl Room {
+    fn new(
+        id: u64,
+        name: String,
+        max_players: usize,
+        on_demand: bool,
+        game: GameConfig,
+        manager: &RoomManager,
+    ) -> Self {
+        let config = &manager.config;
+        let mut game_state = GameState::new(game);
+        game_state.bans = manager.bans.clone();
+        Self {
+            id,
+            name,
+            max_players,
+            on_demand,
+            game_state: Arc::new(Mutex::new(game_state)),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            delta_updates: config.network.delta_updates,
+            keyframe_interval: config.network.keyframe_interval,
+            interest: config.interest.clone(),
+            hall_of_fame: manager.hall_of_fame.clone(),
+        }
+    }
+
+    /// Send lives that ended since the last call to the hall of fame
+    pub async fn record_finished_lives(&self) {
+        let lives = std::mem::take(&mut self.game_state.lock().await.finished_lives);
+        if let (Some(hall_of_fame), false) = (&self.hall_of_fame, lives.is_empty()) {
+            let hall_of_fame = hall_of_fame.clone();
+            tokio::spawn(async move { hall_of_fame.record(lives).await });
+        }
+    }
+
+    /// Phase 3: Broadcast current snapshot to all connected players
+    /// Each client gets the part of the world around its player (interest
+    /// management). Clients that acknowledged a recent snapshot get a delta
+    /// against it, everyone else (and everyone on keyframe ticks) gets the
+    /// full view. Returns the number of bytes queued.
+    pub async fn broadcast_state(&self) -> usize {
+        let snapshot = {
+            let gs = self.game_state.lock().await;
+            gs.to_snapshot()
+        };
+        let keyframe_due = !self.delta_updates || snapshot.tick % self.keyframe_interval == 0;
+        // Keep one keyframe interval of history; older acks get a full snapshot
+        let history_len = if self.delta_updates { self.keyframe_interval as usize } else { 0 };
+
+        let mut bytes = 0;
+        let mut conns = self.connections.lock().await;
+        for (id, conn) in conns.iter_mut() {
+            let view = if self.interest.enabled {
+                interest::visible_snapshot(&snapshot, *id, &self.interest)
+            } else {
+                snapshot.clone()
+            };
+            bytes += conn.send_snapshot(view, keyframe_due, history_len);
+        }
+        bytes
+    }
+
+    /// Take a player out of this room and disconnect its client with `reason`
+    /// False if the player is not here.
+    pub async fn kick(&self, player_id: u64, reason: &str) -> bool {
+        let mut gs = self.game_state.lock().await;
+        if !gs.has_player(player_id) {
+            return false;
+        }
+        gs.remove_player(player_id);
+        if let Some(conn) = self.connections.lock().await.remove(&player_id) {
+            conn.kick(reason);
+        }
+        true
+    }
+
+    /// Full snapshot for a client that just arrived in this room
+    pub fn initial_view(&self, gs: &GameState, player_id: u64) -> shared::GameSnapshot {
+        let snapshot = gs.to_snapshot();
+        if self.interest.enabled {
+            interest::visible_snapshot(&snapshot, player_id, &self.interest)
+        } else {
+            snapshot
+        }
+    }
+
+    async fn info(&self) -> RoomInfo {
+        RoomInfo {
+            id: self.id,
+            name: self.name.clone(),
+            players: self.game_state.lock().await.player_count(),
+            max_players: self.max_players,
+        }
+    }
+}
+
+struct RoomEntry {
+    room: Arc<Room>,
+    tick_task: JoinHandle<()>,
+}
+
+/// Rooms hosted by this server process
+/// Lock order: the room table first, then a room's game state, then its
+/// connections.
+pub struct RoomManager {
+    rooms: Mutex<BTreeMap<u64, RoomEntry>>,
+    next_room_id: Mutex<u64>,
+    config: ServerConfig,
+    pub hall_of_fame: Option<HallOfFame>,
+    pub bans: Arc<BanList>, // set from the admin console, shared with every room
+}
+
+impl RoomManager {
+    /// Open the preset rooms (or a single room "main") and start their ticks
+    pub async fn new(config: &ServerConfig, hall_of_fame: Option<HallOfFame>) -> Self {
+        let manager = Self {
+            rooms: Mutex::new(BTreeMap::new()),
+            next_room_id: Mutex::new(1),
+            config: config.clone(),
+            hall_of_fame,
+            bans: Arc::default(),
+        };
+        {
+            let mut rooms = manager.rooms.lock().await;
+            if config.rooms.presets.is_empty() {
+                let max_players = config.rooms.max_players;
+                manager.open_room(&mut rooms, "main".to_string(), max_players, false, config.game.clone()).await;
+            }
+            for preset in &config.rooms.presets {
+                // Already checked by ServerConfig::validate
+                let game = config.preset_game_config(preset).expect("room preset was validated");
+                let max_players = preset.max_players.unwrap_or(config.rooms.max_players);
+                manager.open_room(&mut rooms, preset.name.clone(), max_players, false, game).await;
+            }
+        }
+        manager
+    }
+
+    async fn open_room(
+        &self,
+        rooms: &mut BTreeMap<u64, RoomEntry>,
+        name: String,
+        max_players: usize,
+        on_demand: bool,
+        game: GameConfig,
+    ) -> Arc<Room> {
+        let id = {
+            let mut next = self.next_room_id.lock().await;
+            let id = *next;
+            *next += 1;
+            id
+        };
+        let room = Arc::new(Room::new(id, name, max_players, on_demand, game, self));
+        let game_loop = GameLoop::new(room.clone());
+        // Everything logged by the tick task carries the room
+        let span = info_span!("room", room = %room.name);
+        let tick_task = tokio::spawn(
+            async move {
+                game_loop.run().await;
+            }
+            .instrument(span),
+        );
+        info!(room_id = id, room = %room.name, max_players, "Room opened");
+        rooms.insert(id, RoomEntry { room: room.clone(), tick_task });
+        room
+    }
+
+    /// Close rooms opened on demand that nobody is in anymore
+    async fn close_empty_rooms(&self, rooms: &mut BTreeMap<u64, RoomEntry>) {
+        let mut empty = Vec::new();
+        for (id, entry) in rooms.iter() {
+            if entry.room.on_demand
+                && entry.room.game_state.lock().await.player_count() == 0
+                && entry.room.connections.lock().await.is_empty()
+            {
+                empty.push(*id);
+            }
+        }
+        for id in empty {
+            if let Some(entry) = rooms.remove(&id) {
+                entry.tick_task.abort();
+                // Lives that ended after the last tick
+                entry.room.record_finished_lives().await;
+                metrics::get().forget_room(&entry.room.name);
+                info!(room_id = id, room = %entry.room.name, "Room closed");
+            }
+        }
+    }
+
+    /// Matchmaking: add a new player to a room whose lobby has a free slot,
+    /// opening a new room if there is none. Only at the room limit does the
+    /// player join a running game (with a free slot, else the least crowded).
+    pub async fn join_open_room(&self, player_id: u64) -> Arc<Room> {
+        let mut rooms = self.rooms.lock().await;
+        self.close_empty_rooms(&mut rooms).await;
+
+        let mut running: Option<Arc<Room>> = None; // first running game with a free slot
+        let mut least_crowded: Option<(usize, Arc<Room>)> = None;
+        for entry in rooms.values() {
+            let mut gs = entry.room.game_state.lock().await;
+            let count = gs.player_count();
+            if count < entry.room.max_players {
+                if gs.status == GameStatus::WaitingToStart {
+                    gs.add_player(player_id);
+                    return entry.room.clone();
+                }
+                running.get_or_insert_with(|| entry.room.clone());
+            }
+            if least_crowded.as_ref().is_none_or(|(c, _)| count < *c) {
+                least_crowded = Some((count, entry.room.clone()));
+            }
+        }
+
+        let at_room_limit = rooms.len() >= self.config.rooms.max_rooms;
+        let room = match (running, least_crowded) {
+            (Some(room), _) if at_room_limit => room,
+            (None, Some((_, room))) if at_room_limit => room,
+            _ => {
+                let name = format!("Arena {}", *self.next_room_id.lock().await);
+                let max_players = self.config.rooms.max_players;
+                self.open_room(&mut rooms, name, max_players, true, self.config.game.clone()).await
+            }
+        };
+        room.game_state.lock().await.add_player(player_id);
+        room
+    }
+
+    /// Add a player to the room with this name, opening it if needed
+    /// Errors are meant for the client (`RoomListMessage::error`).
+    pub async fn join_named_room(&self, name: &str, player_id: u64) -> Result<Arc<Room>, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Room name must not be empty".to_string());
+        }
+        if name.chars().count() > MAX_ROOM_NAME_CHARS {
+            return Err(format!("Room names are at most {} characters", MAX_ROOM_NAME_CHARS));
+        }
+        if !name.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_')) {
+            return Err("Room names may only use letters, digits, spaces, `-` and `_`".to_string());
+        }
+        let mut rooms = self.rooms.lock().await;
+        self.close_empty_rooms(&mut rooms).await;
+
+        let room = match rooms.values().find(|e| e.room.name == name) {
+            Some(entry) => entry.room.clone(),
+            None if rooms.len() >= self.config.rooms.max_rooms => {
+                return Err(format!("Cannot open room `{}`: the server is at its room limit", name));
+            }
+            None => {
+                let max_players = self.config.rooms.max_players;
+                self.open_room(&mut rooms, name.to_string(), max_players, true, self.config.game.clone()).await
+            }
+        };
+
+        let mut gs = room.game_state.lock().await;
+        if gs.player_count() >= room.max_players {
+            return Err(format!("Room `{}` is full", name));
+        }
+        gs.add_player(player_id);
+        drop(gs);
+        Ok(room)
+    }
+
+    /// Room and player id for a resume token (see `GameState::resume_player`)
+    pub async fn resume(&self, token: &str) -> Option<(Arc<Room>, u64)> {
+        let rooms = self.rooms.lock().await;
+        for entry in rooms.values() {
+            if let Some(id) = entry.room.game_state.lock().await.resume_player(token) {
+                return Some((entry.room.clone(), id));
+            }
+        }
+        None
+    }
+
+    /// Called after a player left a room
+    pub async fn player_left(&self) {
+        let mut rooms = self.rooms.lock().await;
+        self.close_empty_rooms(&mut rooms).await;
+    }
+
+    /// Every open room, by id
+    pub async fn rooms(&self) -> Vec<Arc<Room>> {
+        let rooms = self.rooms.lock().await;
+        let mut list: Vec<Arc<Room>> = rooms.values().map(|entry| entry.room.clone()).collect();
+        list.sort_by_key(|room| room.id);
+        list
+    }
+
+    /// Every room with its current player count
+    pub async fn list(&self) -> Vec<RoomInfo> {
+        let rooms = self.rooms.lock().await;
+        let mut list = Vec::with_capacity(rooms.len());
+        for entry in rooms.values() {
+            list.push(entry.room.info().await);
+        }
+        list
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn start_main_round(manager: &RoomManager) {
+        let main = manager.rooms().await.remove(0);
+        main.game_state.lock().await.status = GameStatus::Playing;
+    }
+
+    #[tokio::test]
+    async fn late_joiners_get_a_new_lobby_instead_of_a_running_game() {
+        let manager = RoomManager::new(&ServerConfig::default(), None).await;
+        assert_eq!(manager.join_open_room(1).await.name, "main");
+        start_main_round(&manager).await;
+
+        let room = manager.join_open_room(2).await;
+        assert_eq!(room.name, "Arena 2");
+        assert!(room.on_demand);
+        // The next one waits in the same lobby
+        assert_eq!(manager.join_open_room(3).await.id, room.id);
+    }
+
+    #[tokio::test]
+    async fn late_joiners_join_the_running_game_at_the_room_limit() {
+        let mut config = ServerConfig::default();
+        config.rooms.max_rooms = 1;
+        let manager = RoomManager::new(&config, None).await;
+        start_main_round(&manager).await;
+
+        assert_eq!(manager.join_open_room(1).await.name, "main");
+        assert_eq!(manager.rooms().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn room_names_are_checked_before_opening_a_room() {
+        let manager = RoomManager::new(&ServerConfig::default(), None).await;
+        for name in ["  ", &"x".repeat(MAX_ROOM_NAME_CHARS + 1), "<script>", "a\nb", "dots.and/slashes"] {
+            assert!(manager.join_named_room(name, 1).await.is_err(), "{:?} was accepted", name);
+        }
+        assert_eq!(manager.rooms().await.len(), 1);
+
+        let room = manager.join_named_room(" Zoë room_2-b ", 1).await.unwrap();
+        assert_eq!(room.name, "Zoë room_2-b");
+        assert!(manager.join_named_room(&"x".repeat(MAX_ROOM_NAME_CHARS), 2).await.is_ok());
+    }
+}
@@ -1,87 +1,225 @@
 use tokio::net::TcpListener;
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_tungstenite::{accept_hdr_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
 use futures_util::{StreamExt, SinkExt};
+use std::net::IpAddr;
 use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{Mutex, Notify, mpsc};
+use std::collections::VecDeque;
+use tracing::{debug, field, info, info_span, trace, warn, Instrument, Span};
 
-use shared::protocol::{ClientMessage, ServerMessage, StateUpdateMessage};
+use shared::codec::{self, Frame, WireCodec};
+use shared::delta;
+use shared::protocol::{ClientMessage, ServerMessage, StateUpdateMessage, DeltaUpdateMessage, RoomListMessage, WelcomeMessage, HallOfFameMessage, ByeMessage};
+use shared::GameSnapshot;
+use crate::admin::{self, Admin};
+use crate::api;
+use crate::config::ServerConfig;
 use crate::game_state::GameState;
+use crate::hall_of_fame::HallOfFame;
+use crate::metrics;
+use crate::room_manager::{Room, RoomManager};
 
 pub type Tx = mpsc::UnboundedSender<Message>;
 pub type Rx = mpsc::UnboundedReceiver<Message>;
 
-pub struct WebSocketManager {
-    pub addr: String,
-    pub next_player_id: Arc<Mutex<u64>>,
-    pub game_state: Arc<Mutex<GameState>>,
-    // Phase 3: Connections for broadcasting
-    pub connections: Arc<Mutex<HashMap<u64, Tx>>>,
-}
+// Tells connections apart after a Resume moved one onto another's player id
+static NEXT_CONNECTION_SERIAL: AtomicU64 = AtomicU64::new(1);
 
-impl WebSocketManager {
-    pub async fn new(addr: &str) -> Self {
-        let constants = shared::GameConstant {
-            tick_interval_ms: 50,
-            collide_size_fraction: 1.001,
-            move_speed_base: 150.0,
-            dot_radius: 5.0,
-        };
+/// Outgoing side of one client connection
+pub struct Connection {
+    pub tx: Tx,
+    pub codec: WireCodec,         // negotiated at handshake
+    pub acked_tick: Option<u64>,  // newest snapshot the client confirmed, deltas are based on it
+    epoch: u64,                   // sent in Welcome, echoed in Ack; bumped by reset_history
+    pub ip: IpAddr,               // peer address (for bans)
+    history: VecDeque<GameSnapshot>, // snapshots sent to this client (bases for deltas)
+    serial: u64,
+    kicked: Arc<Notify>,          // wakes the read loop to drop the connection
+}
 
+impl Connection {
+    pub fn new(tx: Tx, codec: WireCodec, ip: IpAddr) -> Self {
+        let serial = NEXT_CONNECTION_SERIAL.fetch_add(1, Ordering::Relaxed);
         Self {
-            addr: addr.to_string(),
-            next_player_id: Arc::new(Mutex::new(1)),
-            game_state: Arc::new(Mutex::new(GameState::new(constants))),
-            connections: Arc::new(Mutex::new(HashMap::new())),
+            tx,
+            codec,
+            acked_tick: None,
+            epoch: 0,
+            ip,
+            history: VecDeque::new(),
+            serial,
+            kicked: Arc::new(Notify::new()),
         }
     }
 
-    /// Phase 3: Broadcast current snapshot to all connected players
-    pub async fn broadcast_state(&self) {
-        let snapshot = {
-            let gs = self.game_state.lock().await;
-            gs.to_snapshot()
+    /// Say goodbye with `reason` and close the connection
+    /// The caller has already taken the player out of its room, so the
+    /// read loop just stops without parking it.
+    pub fn kick(&self, reason: &str) {
+        self.send(&ServerMessage::Bye(ByeMessage { reason: reason.to_string() }));
+        let _ = self.tx.send(Message::Close(None));
+        self.kicked.notify_one();
+    }
+
+    /// Encode a message with this connection's codec and queue it
+    pub fn send(&self, msg: &ServerMessage) -> bool {
+        self.queue(msg).is_some()
+    }
+
+    /// Like `send`, returning the encoded size in bytes
+    fn queue(&self, msg: &ServerMessage) -> Option<usize> {
+        let frame = codec::encode(msg, self.codec);
+        let len = frame.len();
+        self.tx.send(frame_to_message(frame)).ok()?;
+        metrics::get().message_sent(msg);
+        Some(len)
+    }
+
+    /// Send this client its view of the world: a delta against the snapshot
+    /// it acknowledged, or the full view on keyframes / without a usable base.
+    /// The last `history_len` views are kept as bases for later deltas.
+    /// Returns the number of bytes queued.
+    pub fn send_snapshot(&mut self, view: GameSnapshot, keyframe_due: bool, history_len: usize) -> usize {
+        let base = match self.acked_tick {
+            Some(acked) if !keyframe_due => self.history.iter().find(|s| s.tick == acked),
+            _ => None,
         };
-        let msg = ServerMessage::StateUpdate(StateUpdateMessage { snapshot });
-        let text = serde_json::to_string(&msg).unwrap();
-        
-        let conns = self.connections.lock().await;
-        let _tick = if let ServerMessage::StateUpdate(ref state) = msg {
-            state.snapshot.tick
-        } else {
-            0
+        let msg = match base {
+            Some(base) => ServerMessage::DeltaUpdate(DeltaUpdateMessage {
+                delta: delta::diff(base, &view),
+            }),
+            None => ServerMessage::StateUpdate(StateUpdateMessage {
+                snapshot: view.clone(),
+            }),
         };
-        //println!("[DEBUG] Broadcasting state to {} players (tick: {})", conns.len(), _tick);
-        for (_id, tx) in conns.iter() {
-            if tx.send(Message::Text(text.clone())).is_err() {
-                //println!("[DEBUG] Broadcast to player {} failed", id);
-            } else {
-                //println!("[DEBUG] Successfully sent state update to player {}", id);
+        let bytes = self.queue(&msg).unwrap_or(0);
+
+        if history_len > 0 {
+            self.history.push_back(view);
+            while self.history.len() > history_len {
+                self.history.pop_front();
             }
         }
+        bytes
+    }
+
+    /// Greet the client, stamped with the current epoch
+    pub fn welcome(&self, mut msg: WelcomeMessage) -> bool {
+        msg.epoch = self.epoch;
+        self.send(&ServerMessage::Welcome(msg))
+    }
+
+    /// Record a client Ack
+    /// Only acks of this epoch for snapshots still in the history count; late
+    /// acks for the world the connection left (JoinRoom / Resume) are dropped.
+    pub fn acknowledge(&mut self, tick: u64, epoch: u64) {
+        if epoch != self.epoch || !self.history.iter().any(|s| s.tick == tick) {
+            return;
+        }
+        self.acked_tick = Some(self.acked_tick.map_or(tick, |t| t.max(tick)));
+    }
+
+    /// Forget acknowledged snapshots (the client now sees another world)
+    pub fn reset_history(&mut self) {
+        self.epoch += 1;
+        self.acked_tick = None;
+        self.history.clear();
+    }
+}
+
+fn frame_to_message(frame: Frame) -> Message {
+    match frame {
+        Frame::Text(text) => Message::Text(text),
+        Frame::Binary(bytes) => Message::Binary(bytes),
+    }
+}
+
+pub struct WebSocketManager {
+    pub addr: String,
+    pub next_player_id: Arc<Mutex<u64>>,
+    pub rooms: Arc<RoomManager>,
+    pub admin: Admin,     // also reachable at ws://.../admin with the admin token
+    resume_grace_ms: u64, // how long a dropped player waits for a Resume
+}
+
+/// Where one client connection currently plays
+struct ClientSession {
+    id: u64,         // player id (changes on Resume)
+    room: Arc<Room>, // changes on Resume / JoinRoom
+}
+
+impl WebSocketManager {
+    pub async fn new(config: &ServerConfig, hall_of_fame: Option<HallOfFame>) -> Self {
+        let rooms = Arc::new(RoomManager::new(config, hall_of_fame).await);
+        Self {
+            addr: config.network.ws_addr.clone(),
+            next_player_id: Arc::new(Mutex::new(1)),
+            admin: Admin::new(rooms.clone(), config.admin.token.clone()),
+            rooms,
+            resume_grace_ms: config.network.resume_grace_ms,
+        }
     }
 
     /// Phase 3: Accept new connections (renamed from run for clarity)
     pub async fn run_accept_loop(&self) {
         let listener = TcpListener::bind(&self.addr).await.unwrap();
-        println!("Server WebSocket running at ws://{}/", self.addr);
+        info!("Server WebSocket running at ws://{}/", self.addr);
 
         loop {
-            let (stream, _) = listener.accept().await.unwrap();
+            let (stream, peer) = listener.accept().await.unwrap();
 
             let id_counter = self.next_player_id.clone();
-            let gs_state = self.game_state.clone();
-            let connections = self.connections.clone();
+            let rooms = self.rooms.clone();
+            let admin = self.admin.clone();
+            let resume_grace_ms = self.resume_grace_ms;
 
+            // Everything logged for this client carries its address and player id
+            let span = info_span!("conn", %peer, player = field::Empty);
             tokio::spawn(async move {
-                let ws_stream = match accept_async(stream).await {
+                // Read the requested codec from the handshake URL (?codec=binary)
+                // `/admin` is the admin console, for holders of the admin token
+                let mut wire_codec = WireCodec::Json;
+                let mut admin_session = false;
+                #[allow(clippy::result_large_err)] // signature dictated by tungstenite
+                let pick_codec = |req: &Request, resp: Response| {
+                    if req.uri().path() == "/admin" {
+                        let token = req.uri().query().and_then(|q| api::query_param(q, "token"));
+                        let header = req.headers().get("authorization").and_then(|v| v.to_str().ok());
+                        if !admin.authorize(token.as_deref().or(header.and_then(admin::bearer_token))) {
+                            let mut denied = ErrorResponse::new(Some("admin token required".to_string()));
+                            *denied.status_mut() = StatusCode::UNAUTHORIZED;
+                            return Err(denied);
+                        }
+                        admin_session = true;
+                    }
+                    wire_codec = WireCodec::from_query(req.uri().query());
+                    Ok(resp)
+                };
+                let mut ws_stream = match accept_hdr_async(stream, pick_codec).await {
                     Ok(ws) => ws,
                     Err(e) => {
-                        println!("Handshake failed: {:?}", e);
+                        warn!("Handshake failed: {:?}", e);
                         return;
                     }
                 };
 
+                if admin_session {
+                    info!("Admin connected");
+                    admin::serve_websocket(ws_stream, admin).await;
+                    info!("Admin disconnected");
+                    return;
+                }
+                if rooms.bans.is_ip_banned(peer.ip()) {
+                    info!("Banned address refused");
+                    let bye = ServerMessage::Bye(ByeMessage { reason: admin::BAN_REASON.to_string() });
+                    let _ = ws_stream.send(frame_to_message(codec::encode(&bye, wire_codec))).await;
+                    let _ = ws_stream.close(None).await;
+                    return;
+                }
+
                 let (mut ws_tx, mut ws_rx) = ws_stream.split();
 
                 // Phase 3: Create a channel for sending messages to this client
@@ -102,85 +240,360 @@ impl WebSocketManager {
                 *id_guard += 1;
                 drop(id_guard);
 
-                println!("Player {} connected!", id);
+                Span::current().record("player", id);
+                info!(codec = wire_codec.as_str(), "Player connected");
+                metrics::get().connections.inc();
+                let conn = Connection::new(tx, wire_codec, peer.ip());
+                let serial = conn.serial;
+                let kicked = conn.kicked.clone();
 
-                // 2. Add GameState
-                gs_state.lock().await.add_player(id);
-
-                // Phase 3: Register connection for broadcasting
-                connections.lock().await.insert(id, tx.clone());
+                // 2. Add to the GameState of a room with a free slot
+                let room = rooms.join_open_room(id).await;
+                let mut session = ClientSession { id, room };
+                let (welcome_msg, snapshot) = {
+                    let room = &session.room;
+                    let mut gs = room.game_state.lock().await;
+                    let token = gs.issue_resume_token(id);
+                    // Current game snapshot so the new player sees the current state
+                    (welcome_message(room, &gs, id, token), room.initial_view(&gs, id))
+                };
+                info!(room = %session.room.name, "Player entered room");
 
                 // Send Welcome message to the new player
-                let welcome_msg = ServerMessage::Welcome(shared::protocol::WelcomeMessage {
-                    player_id: id,
-                    constants: {
-                        let gs = gs_state.lock().await;
-                        gs.constants.clone()
-                    },
-                });
-                let welcome_text = serde_json::to_string(&welcome_msg).unwrap();
-                if tx.send(Message::Text(welcome_text)).is_err() {
-                    println!("Failed to send Welcome message to player {}", id);
+                if !conn.welcome(welcome_msg) {
+                    warn!("Failed to send Welcome message");
                 }
-
-                // Send current game snapshot to the new player so they see the current state
-                {
-                    let gs = gs_state.lock().await;
-                    let snapshot = gs.to_snapshot();
-                    let state_msg = ServerMessage::StateUpdate(shared::protocol::StateUpdateMessage { snapshot });
-                    let state_text = serde_json::to_string(&state_msg).unwrap();
-                    if tx.send(Message::Text(state_text)).is_err() {
-                        println!("Failed to send initial state update to player {}", id);
-                    }
+                let state_msg = ServerMessage::StateUpdate(StateUpdateMessage { snapshot });
+                if !conn.send(&state_msg) {
+                    warn!("Failed to send initial state update");
                 }
 
+                // Phase 3: Register connection for broadcasting
+                // (after the Welcome so it is always the first message the client sees)
+                session.room.connections.lock().await.insert(id, conn);
+
                 // 3. Read Message
-                // No matter Close or Error, remove player eventually
-                while let Some(msg_result) = ws_rx.next().await {
+                // No matter Close, Error or a kick, remove player eventually
+                loop {
+                    let msg_result = tokio::select! {
+                        msg = ws_rx.next() => match msg {
+                            Some(msg) => msg,
+                            None => break,
+                        },
+                        _ = kicked.notified() => {
+                            info!("Player kicked");
+                            break;
+                        }
+                    };
                     match msg_result {
                         Ok(Message::Text(txt)) => {
-                            println!("Raw text from {}: {}", id, txt);
-                            match serde_json::from_str::<ClientMessage>(&txt) {
-                                Ok(client_msg) => {
-                                    println!("Parsed ClientMessage from {}: {:?}", id, client_msg);
-                                    
-                                    // Handle Quit message by closing the connection
-                                    if matches!(client_msg, ClientMessage::Quit) {
-                                        println!("Player {} requested to quit, closing connection", id);
-                                        gs_state.lock().await.remove_player(id);
-                                        connections.lock().await.remove(&id);
-                                        break;
-                                    }
-                                    
-                                    gs_state.lock().await.handle_message(id, client_msg);
-                                }
-                                Err(e) => {
-                                    println!(
-                                        "Failed to parse ClientMessage from {}: {:?}",
-                                        id, e
-                                    );
-                                }
+                            trace!(text = %txt, "Raw text message");
+                            let parsed = codec::decode_text::<ClientMessage>(&txt)
+                                .map_err(|e| e.to_string());
+                            if handle_client_message(&mut session, parsed, &rooms).await {
+                                break;
+                            }
+                        }
+                        Ok(Message::Binary(bytes)) => {
+                            let parsed = codec::decode_binary::<ClientMessage>(&bytes)
+                                .map_err(|e| e.to_string());
+                            if handle_client_message(&mut session, parsed, &rooms).await {
+                                break;
                             }
                         }
                         Ok(Message::Close(frame)) => {
-                            println!("Player {} sent Close frame: {:?}", id, frame);
+                            debug!("Close frame: {:?}", frame);
                             break;
                         }
                         Ok(other) => {
-                            println!("Other WS message from {}: {:?}", id, other);
+                            trace!("Other WS message: {:?}", other);
                         }
                         Err(e) => {
-                            println!("WebSocket error from {}: {:?}", id, e);
+                            warn!("WebSocket error: {:?}", e);
                             break;
                         }
                     }
                 }
 
-                // 4. Remove Player from GameState
-                println!("Cleaning up player {} from GameState", id);
-                gs_state.lock().await.remove_player(id);
-                connections.lock().await.remove(&id);
+                // 4. Keep the player for a Resume, or remove it from GameState
+                // (unless a resumed connection has taken it over meanwhile)
+                let ClientSession { id, room } = session;
+                let still_ours = {
+                    let mut conns = room.connections.lock().await;
+                    let ours = conns.get(&id).is_some_and(|c| c.serial == serial);
+                    if ours {
+                        conns.remove(&id);
+                    }
+                    ours
+                };
+                if still_ours {
+                    let mut gs = room.game_state.lock().await;
+                    let grace_ticks = resume_grace_ms / gs.constants.tick_interval_ms;
+                    if grace_ticks > 0 && gs.park_player(id, grace_ticks) {
+                        info!(grace_ticks, "Player disconnected, waiting for a resume");
+                    } else {
+                        info!("Player disconnected, removing it");
+                        gs.remove_player(id);
+                    }
+                }
+                rooms.player_left().await;
+                metrics::get().connections.dec();
+            }.instrument(span));
+        }
+    }
+}
+
+/// Welcome for a player that (re)enters `room` (epoch set by `Connection::welcome`)
+fn welcome_message(room: &Room, gs: &GameState, player_id: u64, resume_token: String) -> WelcomeMessage {
+    WelcomeMessage {
+        player_id,
+        constants: gs.constants.clone(),
+        resume_token,
+        room: room.name.clone(),
+        epoch: 0,
+    }
+}
+
+/// Move the session's connection into `to` as player `new_id`, greeting it there
+/// A connection still registered under `new_id` in `to` (the client resumed
+/// before its old socket dropped) is kicked, so only one client controls the player.
+/// The snapshot history belongs to the old world, so the next update is full.
+async fn move_connection(session: &mut ClientSession, to: Arc<Room>, new_id: u64, welcome_msg: WelcomeMessage) {
+    let conn = session.room.connections.lock().await.remove(&session.id);
+    if let Some(mut conn) = conn {
+        conn.reset_history();
+        conn.welcome(welcome_msg);
+        if let Some(stale) = to.connections.lock().await.insert(new_id, conn) {
+            info!(player = new_id, "Dropping the connection this player resumed from");
+            stale.kick("Resumed from another connection");
+        }
+    }
+    session.id = new_id;
+    session.room = to;
+}
+
+/// True if player `id` of `room` goes by a name that is banned
+async fn has_banned_name(room: &Room, id: u64, rooms: &RoomManager) -> bool {
+    let gs = room.game_state.lock().await;
+    gs.players.get(&id).is_some_and(|p| rooms.bans.is_name_banned(&p.name))
+}
+
+/// Send the room list to this session's client
+async fn send_room_list(session: &ClientSession, rooms: &RoomManager, error: Option<String>) {
+    let msg = ServerMessage::RoomList(RoomListMessage {
+        rooms: rooms.list().await,
+        current: session.room.name.clone(),
+        error,
+    });
+    if let Some(conn) = session.room.connections.lock().await.get(&session.id) {
+        conn.send(&msg);
+    }
+}
+
+/// Dispatch one decoded client message
+/// Returns true if the connection should be closed.
+/// Resume and JoinRoom move the session to another player id / room.
+async fn handle_client_message(
+    session: &mut ClientSession,
+    parsed: Result<ClientMessage, String>,
+    rooms: &RoomManager,
+) -> bool {
+    let id = session.id;
+    metrics::get().message_received(parsed.as_ref().ok());
+    match parsed {
+        Ok(ClientMessage::Ack { tick, epoch }) => {
+            // Sent every tick, so no logging here
+            if let Some(conn) = session.room.connections.lock().await.get_mut(&id) {
+                conn.acknowledge(tick, epoch);
+            }
+            false
+        }
+        Ok(ClientMessage::Resume { token }) => {
+            let (room, old_id) = match rooms.resume(&token).await {
+                Some((_, old_id)) if old_id == id => return false, // already playing as this player
+                Some(found) => found,
+                None => {
+                    info!("Unknown or expired resume token");
+                    return false;
+                }
+            };
+            if has_banned_name(&room, old_id, rooms).await {
+                info!(old_player = old_id, "Banned name refused on resume");
+                room.kick(old_id, admin::BAN_REASON).await;
+                session.room.kick(id, admin::BAN_REASON).await;
+                rooms.player_left().await;
+                return true;
+            }
+            // The fresh player made for this connection is not needed
+            session.room.game_state.lock().await.remove_player(id);
+            let welcome_msg = welcome_message(&room, &*room.game_state.lock().await, old_id, token);
+            info!(old_player = old_id, room = %room.name, "Player resumed");
+            Span::current().record("player", old_id);
+            move_connection(session, room, old_id, welcome_msg).await;
+            rooms.player_left().await;
+            false
+        }
+        Ok(ClientMessage::Join { name }) if rooms.bans.is_name_banned(&name) => {
+            info!(name = %name, "Banned name refused");
+            session.room.kick(id, admin::BAN_REASON).await;
+            true
+        }
+        Ok(ClientMessage::ListRooms) => {
+            send_room_list(session, rooms, None).await;
+            false
+        }
+        Ok(ClientMessage::HallOfFame) => {
+            let top = match &rooms.hall_of_fame {
+                Some(hall_of_fame) => hall_of_fame.top().await,
+                None => HallOfFameMessage::default(),
+            };
+            if let Some(conn) = session.room.connections.lock().await.get(&id) {
+                conn.send(&ServerMessage::HallOfFame(top));
+            }
+            false
+        }
+        Ok(ClientMessage::JoinRoom { room: name }) => {
+            if name.trim() == session.room.name {
+                send_room_list(session, rooms, None).await;
+                return false;
+            }
+            // The name is carried over, so it must not have been banned since the Join
+            if has_banned_name(&session.room, id, rooms).await {
+                info!("Banned name refused on room change");
+                session.room.kick(id, admin::BAN_REASON).await;
+                return true;
+            }
+            let room = match rooms.join_named_room(&name, id).await {
+                Ok(room) => room,
+                Err(e) => {
+                    info!(room = name.trim(), "Cannot join room: {}", e);
+                    send_room_list(session, rooms, Some(e)).await;
+                    return false;
+                }
+            };
+            // Start over in the new room, keeping the name
+            let old_name = {
+                let mut gs = session.room.game_state.lock().await;
+                let old_name = gs.players.get(&id).map(|p| p.name.clone());
+                gs.remove_player(id);
+                old_name
+            };
+            let welcome_msg = {
+                let mut gs = room.game_state.lock().await;
+                if let Some(name) = old_name {
+                    gs.handle_message(id, ClientMessage::Join { name });
+                }
+                let token = gs.issue_resume_token(id);
+                welcome_message(&room, &gs, id, token)
+            };
+            info!(from = %session.room.name, to = %room.name, "Player moved to another room");
+            move_connection(session, room, id, welcome_msg).await;
+            rooms.player_left().await;
+            false
+        }
+        Ok(client_msg) => {
+            debug!("Client message: {:?}", client_msg);
+
+            // Handle Quit message by closing the connection
+            if matches!(client_msg, ClientMessage::Quit) {
+                info!("Player quit, closing connection");
+                session.room.game_state.lock().await.remove_player(id);
+                session.room.connections.lock().await.remove(&id);
+                return true;
+            }
+
+            session.room.game_state.lock().await.handle_message(id, client_msg);
+            false
+        }
+        Err(e) => {
+            warn!("Failed to parse client message: {}", e);
+            false
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    async fn connect(room: &Room, id: u64) -> Rx {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let conn = Connection::new(tx, WireCodec::Json, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        room.connections.lock().await.insert(id, conn);
+        rx
+    }
+
+    fn received(rx: &mut Rx) -> Vec<String> {
+        let mut kinds = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            kinds.push(match msg {
+                Message::Text(text) => match codec::decode_text::<ServerMessage>(&text).unwrap() {
+                    ServerMessage::Welcome(_) => "welcome".to_string(),
+                    ServerMessage::Bye(bye) => format!("bye: {}", bye.reason),
+                    other => format!("{:?}", other),
+                },
+                Message::Close(_) => "close".to_string(),
+                other => format!("{:?}", other),
             });
         }
+        kinds
+    }
+
+    #[tokio::test]
+    async fn resuming_a_connected_player_kicks_the_old_connection() {
+        let rooms = RoomManager::new(&ServerConfig::default(), None).await;
+        let room = rooms.join_open_room(1).await;
+        let token = room.game_state.lock().await.issue_resume_token(1);
+        let mut old = connect(&room, 1).await;
+
+        // Same client on a new socket, before the old one noticed the drop
+        rooms.join_open_room(2).await;
+        let mut new = connect(&room, 2).await;
+        let mut session = ClientSession { id: 2, room: room.clone() };
+        assert!(!handle_client_message(&mut session, Ok(ClientMessage::Resume { token }), &rooms).await);
+
+        assert_eq!(session.id, 1);
+        assert_eq!(received(&mut new), vec!["welcome"]);
+        assert_eq!(received(&mut old), vec!["bye: Resumed from another connection", "close"]);
+        let conns = room.connections.lock().await;
+        assert_eq!(conns.keys().collect::<Vec<_>>(), vec![&1]);
+        assert!(!room.game_state.lock().await.has_player(2));
+    }
+
+    #[tokio::test]
+    async fn banned_names_cannot_change_rooms_or_resume() {
+        let rooms = Arc::new(RoomManager::new(&ServerConfig::default(), None).await);
+        Admin::new(rooms.clone(), None).execute("ban name bob").await;
+        let room = rooms.join_open_room(1).await;
+        rooms.join_open_room(2).await;
+        let mut conn = connect(&room, 1).await;
+        let token = {
+            // Named before the ban reached this room
+            let mut gs = room.game_state.lock().await;
+            for id in [1, 2] {
+                gs.players.get_mut(&id).unwrap().name = "Bob".to_string();
+            }
+            gs.park_player(2, 100);
+            gs.issue_resume_token(2)
+        };
+        let bye = vec![format!("bye: {}", admin::BAN_REASON), "close".to_string()];
+
+        let mut session = ClientSession { id: 1, room: room.clone() };
+        let join = ClientMessage::JoinRoom { room: "elsewhere".to_string() };
+        assert!(handle_client_message(&mut session, Ok(join), &rooms).await);
+        assert_eq!(received(&mut conn), bye);
+        assert!(!room.game_state.lock().await.has_player(1));
+        assert_eq!(rooms.rooms().await.len(), 1);
+
+        rooms.join_open_room(3).await;
+        let mut conn = connect(&room, 3).await;
+        let mut session = ClientSession { id: 3, room: room.clone() };
+        assert!(handle_client_message(&mut session, Ok(ClientMessage::Resume { token }), &rooms).await);
+        assert_eq!(received(&mut conn), bye);
+        let gs = room.game_state.lock().await;
+        assert!(!gs.has_player(2));
+        assert!(!gs.has_player(3));
     }
 }
@@ -0,0 +1,144 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+
+/// Wire Codec
+/// Encoding used on one WebSocket connection.
+///    - Json:   text frames, human readable (debugging, browser tools)
+///    - Binary: bincode in binary frames, much smaller snapshots
+///
+/// Chosen by the client with the `codec` query parameter of the connect URL,
+/// e.g. `ws://host:34568/?codec=binary`. Anything else falls back to Json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WireCodec {
+    #[default]
+    Json,
+    Binary,
+}
+
+impl WireCodec {
+    /// Query parameter value for this codec
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireCodec::Json => "json",
+            WireCodec::Binary => "binary",
+        }
+    }
+
+    /// Parse a codec name ("json" / "binary"), case-insensitive
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(WireCodec::Json),
+            "binary" | "bincode" => Some(WireCodec::Binary),
+            _ => None,
+        }
+    }
+
+    /// Pick the codec from a URL query string such as `codec=binary&x=1`
+    pub fn from_query(query: Option<&str>) -> Self {
+        query
+            .unwrap_or("")
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "codec")
+            .and_then(|(_, value)| Self::parse(value))
+            .unwrap_or_default()
+    }
+}
+
+
+/// Encoded message, mapped 1:1 onto WebSocket text / binary frames
+#[derive(Debug, Clone)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl Frame {
+    /// Payload size in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            Frame::Text(text) => text.len(),
+            Frame::Binary(bytes) => bytes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+
+/// Encode a protocol message with the given codec
+pub fn encode<T: Serialize>(msg: &T, codec: WireCodec) -> Frame {
+    match codec {
+        // Protocol types only contain plain data, serialization cannot fail
+        WireCodec::Json => Frame::Text(serde_json::to_string(msg).expect("JSON encoding failed")),
+        WireCodec::Binary => Frame::Binary(bincode::serialize(msg).expect("bincode encoding failed")),
+    }
+}
+
+/// Decode a message received in a text frame
+pub fn decode_text<T: DeserializeOwned>(text: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(text)
+}
+
+/// Decode a message received in a binary frame
+pub fn decode_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, bincode::Error> {
+    bincode::deserialize(bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ByeMessage, ClientMessage, ServerMessage};
+
+    fn decode<T: DeserializeOwned>(frame: &Frame) -> T {
+        match frame {
+            Frame::Text(text) => decode_text(text).unwrap(),
+            Frame::Binary(bytes) => decode_binary(bytes).unwrap(),
+        }
+    }
+
+    #[test]
+    fn messages_round_trip_with_both_codecs() {
+        let join = ClientMessage::Join { name: "Zoë".to_string() };
+        let bye = ServerMessage::Bye(ByeMessage { reason: "bye".to_string() });
+        for codec in [WireCodec::Json, WireCodec::Binary] {
+            let frame = encode(&join, codec);
+            assert_eq!(matches!(frame, Frame::Binary(_)), codec == WireCodec::Binary);
+            assert_eq!(format!("{:?}", decode::<ClientMessage>(&frame)), format!("{:?}", join));
+
+            let frame = encode(&bye, codec);
+            assert!(!frame.is_empty());
+            assert_eq!(format!("{:?}", decode::<ServerMessage>(&frame)), format!("{:?}", bye));
+        }
+    }
+
+    #[test]
+    fn garbage_does_not_decode() {
+        assert!(decode_text::<ClientMessage>("{\"Join\":{}}").is_err());
+        assert!(decode_binary::<ClientMessage>(&[0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn codec_is_picked_from_the_query_string() {
+        assert_eq!(WireCodec::from_query(Some("codec=binary")), WireCodec::Binary);
+        assert_eq!(WireCodec::from_query(Some("name=x&codec=BINCODE")), WireCodec::Binary);
+        assert_eq!(WireCodec::from_query(Some("codec=json&codec=binary")), WireCodec::Json);
+        // Unknown or missing codecs fall back to JSON
+        assert_eq!(WireCodec::from_query(Some("codec=xml")), WireCodec::Json);
+        assert_eq!(WireCodec::from_query(Some("codec")), WireCodec::Json);
+        assert_eq!(WireCodec::from_query(Some("")), WireCodec::Json);
+        assert_eq!(WireCodec::from_query(None), WireCodec::Json);
+    }
+
+    #[test]
+    fn codec_names_parse_back() {
+        for codec in [WireCodec::Json, WireCodec::Binary] {
+            assert_eq!(WireCodec::parse(codec.as_str()), Some(codec));
+        }
+        assert_eq!(WireCodec::parse("JSON"), Some(WireCodec::Json));
+        assert_eq!(WireCodec::parse("msgpack"), None);
+    }
+}
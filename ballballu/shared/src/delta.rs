@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use crate::{GameConstant, GameSnapshot, GameStatus, LobbyState, RoundState};
+use crate::objects::{PlayerSpec, Dot, Virus, LeaderboardEntry, TeamScore};
+
+
+/// Snapshot Delta
+/// Changes between two snapshots, sent instead of a full snapshot once the
+/// client has acknowledged `base_tick`.
+///    - players / dots / viruses that are new or changed are sent whole
+///    - players / dots / viruses that disappeared are sent by id
+///    - leaderboard, teams, lobby, round and constants only when they changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDelta {
+    pub base_tick: u64,                 // snapshot the client must already have
+    pub tick: u64,                      // tick of the resulting snapshot
+    pub status: GameStatus,
+    pub players_changed: Vec<PlayerSpec>, // joined or moved
+    pub players_removed: Vec<u64>,        // left
+    pub dots_changed: Vec<Dot>,           // spawned (or moved)
+    pub dots_removed: Vec<u64>,           // eaten
+    pub viruses_changed: Vec<Virus>,      // spawned, fed or moving
+    pub viruses_removed: Vec<u64>,        // popped
+    pub leaderboard: Option<Vec<LeaderboardEntry>>, // only when it changed
+    pub teams: Option<Vec<TeamScore>>,              // only when it changed
+    pub lobby: Option<LobbyState>,                  // only when it changed
+    pub round: Option<RoundState>,                  // only when it changed
+    pub constants: Option<GameConstant>,            // only when it changed (admin `set`)
+}
+
+impl SnapshotDelta {
+    /// True if nothing but the tick changed
+    pub fn is_empty(&self) -> bool {
+        self.players_changed.is_empty()
+            && self.players_removed.is_empty()
+            && self.dots_changed.is_empty()
+            && self.dots_removed.is_empty()
+            && self.viruses_changed.is_empty()
+            && self.viruses_removed.is_empty()
+            && self.leaderboard.is_none()
+            && self.teams.is_none()
+            && self.lobby.is_none()
+            && self.round.is_none()
+            && self.constants.is_none()
+    }
+}
+
+
+/// Compute the delta that turns `base` into `current`
+pub fn diff(base: &GameSnapshot, current: &GameSnapshot) -> SnapshotDelta {
+    let base_players: HashMap<u64, &PlayerSpec> = base.players.iter().map(|p| (p.id, p)).collect();
+    let current_players: HashSet<u64> = current.players.iter().map(|p| p.id).collect();
+    let base_dots: HashMap<u64, &Dot> = base.dots.iter().map(|d| (d.id, d)).collect();
+    let current_dots: HashSet<u64> = current.dots.iter().map(|d| d.id).collect();
+    let base_viruses: HashMap<u64, &Virus> = base.viruses.iter().map(|v| (v.id, v)).collect();
+    let current_viruses: HashSet<u64> = current.viruses.iter().map(|v| v.id).collect();
+
+    SnapshotDelta {
+        base_tick: base.tick,
+        tick: current.tick,
+        status: current.status,
+        players_changed: current
+            .players
+            .iter()
+            .filter(|p| base_players.get(&p.id).is_none_or(|old| *old != *p))
+            .cloned()
+            .collect(),
+        players_removed: base
+            .players
+            .iter()
+            .filter(|p| !current_players.contains(&p.id))
+            .map(|p| p.id)
+            .collect(),
+        dots_changed: current
+            .dots
+            .iter()
+            .filter(|d| base_dots.get(&d.id).is_none_or(|old| *old != *d))
+            .cloned()
+            .collect(),
+        dots_removed: base
+            .dots
+            .iter()
+            .filter(|d| !current_dots.contains(&d.id))
+            .map(|d| d.id)
+            .collect(),
+        viruses_changed: current
+            .viruses
+            .iter()
+            .filter(|v| base_viruses.get(&v.id).is_none_or(|old| *old != *v))
+            .cloned()
+            .collect(),
+        viruses_removed: base
+            .viruses
+            .iter()
+            .filter(|v| !current_viruses.contains(&v.id))
+            .map(|v| v.id)
+            .collect(),
+        leaderboard: (base.leaderboard != current.leaderboard).then(|| current.leaderboard.clone()),
+        teams: (base.teams != current.teams).then(|| current.teams.clone()),
+        lobby: (base.lobby != current.lobby).then(|| current.lobby.clone()),
+        round: (base.round != current.round).then(|| current.round.clone()),
+        constants: (base.constants != current.constants).then(|| current.constants.clone()),
+    }
+}
+
+
+/// Rebuild the full snapshot from `base` + `delta`
+/// `base.tick` must equal `delta.base_tick`
+pub fn apply(base: &GameSnapshot, delta: &SnapshotDelta) -> GameSnapshot {
+    let removed_players: HashSet<u64> = delta.players_removed.iter().copied().collect();
+    let mut players = base.players.clone();
+    players.retain(|p| !removed_players.contains(&p.id));
+    for changed in &delta.players_changed {
+        match players.iter_mut().find(|p| p.id == changed.id) {
+            Some(existing) => *existing = changed.clone(),
+            None => players.push(changed.clone()),
+        }
+    }
+
+    let removed_dots: HashSet<u64> = delta.dots_removed.iter().copied().collect();
+    let mut dots = base.dots.clone();
+    dots.retain(|d| !removed_dots.contains(&d.id));
+    let mut index: HashMap<u64, usize> = dots.iter().enumerate().map(|(i, d)| (d.id, i)).collect();
+    for changed in &delta.dots_changed {
+        match index.get(&changed.id) {
+            Some(&i) => dots[i] = changed.clone(),
+            None => {
+                index.insert(changed.id, dots.len());
+                dots.push(changed.clone());
+            }
+        }
+    }
+
+    let removed_viruses: HashSet<u64> = delta.viruses_removed.iter().copied().collect();
+    let mut viruses = base.viruses.clone();
+    viruses.retain(|v| !removed_viruses.contains(&v.id));
+    for changed in &delta.viruses_changed {
+        match viruses.iter_mut().find(|v| v.id == changed.id) {
+            Some(existing) => *existing = changed.clone(),
+            None => viruses.push(changed.clone()),
+        }
+    }
+
+    GameSnapshot {
+        tick: delta.tick,
+        status: delta.status,
+        players,
+        dots,
+        viruses,
+        leaderboard: delta.leaderboard.clone().unwrap_or_else(|| base.leaderboard.clone()),
+        teams: delta.teams.clone().unwrap_or_else(|| base.teams.clone()),
+        lobby: delta.lobby.clone().unwrap_or_else(|| base.lobby.clone()),
+        round: delta.round.clone().unwrap_or_else(|| base.round.clone()),
+        constants: delta.constants.clone().unwrap_or_else(|| base.constants.clone()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MovementMode;
+    use crate::objects::{Cell, LobbyEntry};
+
+    fn player(id: u64, x: f32, score: u32) -> PlayerSpec {
+        PlayerSpec {
+            id,
+            name: format!("p{}", id),
+            x,
+            y: 100.0,
+            radius: 20.0,
+            score,
+            speed: 5.0,
+            sequence_number: 0,
+            remaining_distance: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            cells: vec![Cell { id: id * 10, x, y: 100.0, radius: 20.0, score, boost_vx: 0.0, boost_vy: 0.0, merge_tick: 0 }],
+            team: None,
+            bot: false,
+        }
+    }
+
+    fn dot(id: u64, x: f32) -> Dot {
+        Dot { id, x, y: 50.0, radius: 5.0, color: (255, 0, 0), score: 2, vx: 0.0, vy: 0.0 }
+    }
+
+    fn virus(id: u64, fed: u32) -> Virus {
+        Virus { id, x: 300.0, y: 300.0, radius: 60.0, fed, vx: 0.0, vy: 0.0 }
+    }
+
+    fn entry(p: &PlayerSpec) -> LeaderboardEntry {
+        LeaderboardEntry { id: p.id, name: p.name.clone(), score: p.score, team: None, bot: false }
+    }
+
+    fn snapshot(tick: u64) -> GameSnapshot {
+        let players = vec![player(1, 100.0, 10), player(2, 200.0, 20), player(3, 300.0, 30)];
+        GameSnapshot {
+            tick,
+            status: GameStatus::WaitingToStart,
+            leaderboard: players.iter().rev().map(entry).collect(),
+            players,
+            dots: (1..=5).map(|id| dot(id, id as f32 * 10.0)).collect(),
+            viruses: vec![virus(1, 0), virus(2, 0)],
+            teams: Vec::new(),
+            lobby: LobbyState {
+                min_players: 2,
+                countdown_ms: None,
+                roster: vec![LobbyEntry { id: 1, name: "p1".to_string(), ready: false, team: None }],
+            },
+            round: RoundState::default(),
+            constants: GameConstant {
+                tick_interval_ms: 50,
+                collide_size_fraction: 1.1,
+                move_speed_base: 5.0,
+                dot_radius: 5.0,
+                world_width: 1000.0,
+                world_height: 1000.0,
+                movement_mode: MovementMode::Continuous,
+                team_count: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn unchanged_snapshot_gives_empty_delta() {
+        let base = snapshot(10);
+        let current = snapshot(11);
+        let delta = diff(&base, &current);
+        assert!(delta.is_empty());
+        assert_eq!(apply(&base, &delta), current);
+    }
+
+    #[test]
+    fn round_trip_with_changed_added_and_removed_entities() {
+        let base = snapshot(10);
+        let mut current = snapshot(11);
+        current.status = GameStatus::Playing;
+        current.players[0].x += 5.0; // moved
+        current.players.remove(1); // left
+        current.players.push(player(4, 400.0, 5)); // joined
+        current.dots.retain(|d| d.id != 2 && d.id != 4); // eaten
+        current.dots[0].x += 1.0; // moving pellet
+        current.dots.push(dot(6, 60.0)); // spawned
+        current.viruses.remove(0); // popped
+        current.viruses[0].fed = 3; // fed
+        current.viruses.push(virus(3, 0)); // fired
+
+        let delta = diff(&base, &current);
+        assert_eq!(delta.players_removed, vec![2]);
+        assert_eq!(delta.dots_removed, vec![2, 4]);
+        assert_eq!(delta.viruses_removed, vec![1]);
+        assert_eq!(delta.players_changed.iter().map(|p| p.id).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(apply(&base, &delta), current);
+    }
+
+    #[test]
+    fn round_trip_with_leaderboard_lobby_and_round_changes() {
+        let base = snapshot(10);
+        let mut current = snapshot(11);
+        current.leaderboard.reverse();
+        current.lobby.roster[0].ready = true;
+        current.lobby.countdown_ms = Some(3000);
+        current.round.number = 1;
+        current.round.ends_at_tick = Some(1200);
+
+        let delta = diff(&base, &current);
+        assert!(delta.leaderboard.is_some());
+        assert!(delta.lobby.is_some());
+        assert!(delta.round.is_some());
+        assert!(delta.teams.is_none());
+        assert!(delta.constants.is_none());
+        assert_eq!(apply(&base, &delta), current);
+    }
+
+    #[test]
+    fn round_trip_with_changed_constants() {
+        let base = snapshot(10);
+        let mut current = snapshot(11);
+        current.constants.move_speed_base = 8.0;
+
+        let delta = diff(&base, &current);
+        assert_eq!(delta.constants.as_ref(), Some(&current.constants));
+        assert!(!delta.is_empty());
+        assert_eq!(apply(&base, &delta), current);
+    }
+
+    #[test]
+    fn round_trip_to_empty_world() {
+        let base = snapshot(10);
+        let mut current = snapshot(11);
+        current.players.clear();
+        current.dots.clear();
+        current.viruses.clear();
+        current.leaderboard.clear();
+        current.lobby = LobbyState::default();
+
+        assert_eq!(apply(&base, &diff(&base, &current)), current);
+    }
+}
@@ -1,9 +1,14 @@
 use serde::{Serialize, Deserialize};
-use crate::objects::{PlayerSpec, Dot};
+use crate::objects::{PlayerSpec, Dot, Virus, LeaderboardEntry, LobbyEntry, RoundResult, TeamScore};
 
 pub mod mechanics;
 pub mod protocol;
 pub mod objects;
+pub mod codec;
+pub mod delta;
+pub mod spatial;
+#[cfg(feature = "logging")]
+pub mod logging;
 
 
 /// Game Status Enum
@@ -12,32 +17,87 @@ pub mod objects;
 pub enum GameStatus {
     WaitingToStart,  // Start screen, waiting for players to press space
     Playing,         // Game is running
-    GameOver,        // Game has ended
+    GameOver,        // Round has ended, showing results until the next round
 }
 
+/// Movement Mode
+/// How players steer, chosen by the server per game mode
+///    - Discrete:   arrow keys send fixed-distance `Move` steps
+///    - Continuous: the client streams the mouse direction as `Input`,
+///      the server moves the player towards it every tick
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MovementMode {
+    #[default]
+    Discrete,
+    Continuous,
+}
+
+/// Most teams a team mode game can have (clients have a color for each)
+pub const MAX_TEAMS: usize = 8;
+
 /// Game Constants
 /// Core game constants used by both client and server
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameConstant {
     pub tick_interval_ms: u64,      // usually 20 ticks/sec = 50ms
     pub collide_size_fraction: f32, // size ratio needed to consume another player
     pub move_speed_base: f32,       // player default speed
     pub dot_radius: f32,            // constant dot size
+    pub world_width: f32,           // world spans x in [0, world_width]
+    pub world_height: f32,          // world spans y in [0, world_height]
+    pub movement_mode: MovementMode, // which input messages the client should send
+    pub team_count: usize,          // 0 = free-for-all, otherwise teams 0..team_count
+}
+
+
+/// Lobby State
+/// Who is waiting for the game to start, sent with every snapshot
+/// The game starts `countdown_ms` after at least `min_players` players are
+/// in the room and all of them are ready; the countdown stops if that is no
+/// longer true.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LobbyState {
+    pub min_players: usize,
+    pub countdown_ms: Option<u64>, // time left until the game starts, None = not counting down
+    pub roster: Vec<LobbyEntry>,   // every player in the room, in join order
+}
+
+
+/// Round State
+/// Timing of the current round and, once it is over, the final standings
+/// Times are absolute ticks (compare with `GameSnapshot::tick`), so this
+/// only changes when a round starts or ends.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoundState {
+    pub number: u64,                     // rounds started in this room, 0 = none yet
+    pub ends_at_tick: Option<u64>,       // Playing: round ends at this tick, None = no time limit
+    pub next_round_at_tick: Option<u64>, // GameOver: the world resets at this tick
+    pub results: Vec<RoundResult>,       // GameOver: every player, best first
 }
 
 
 /// Game Snapshot
 /// Snapshot of game world sent from server → client every tick
 /// Server sends:
-///    - current state of all players
-///    - all dots
+///    - current state of all players (near the receiver, see interest management)
+///    - all dots (near the receiver)
+///    - viruses (near the receiver)
+///    - global top players (and team scores in team mode)
+///    - lobby roster and countdown
+///    - round timer and results
 ///    - universal game constants
 ///    - current tick (optional)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameSnapshot {
     pub tick: u64,
     pub status: GameStatus,  // Add game status
     pub players: Vec<PlayerSpec>,
     pub dots: Vec<Dot>,
+    pub viruses: Vec<Virus>,
+    pub leaderboard: Vec<LeaderboardEntry>,  // global top players, highest score first
+    pub teams: Vec<TeamScore>,               // team mode: every team, highest score first
+    pub lobby: LobbyState,
+    pub round: RoundState,
     pub constants: GameConstant,
 }
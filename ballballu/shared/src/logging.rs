@@ -0,0 +1,43 @@
+use std::fs::OpenOptions;
+use std::path::Path;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+pub use tracing_appender::non_blocking::WorkerGuard;
+
+/// Install the global `tracing` subscriber
+/// Human-readable lines go to stdout; with `json_file` set, the same events
+/// are also appended to that file as JSON lines. `RUST_LOG` takes precedence
+/// over `filter`. Keep the returned guard alive until exit, it flushes the file.
+pub fn init(filter: &str, json_file: Option<&Path>) -> Result<Option<WorkerGuard>, String> {
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) => directives,
+        Err(_) => filter.to_string(),
+    };
+    let make_filter = || EnvFilter::try_new(&filter).map_err(|e| format!("invalid log filter `{}`: {}", filter, e));
+
+    let console = fmt::layer().with_filter(make_filter()?);
+
+    let (json, guard) = match json_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("cannot open log file {}: {}", path.display(), e))?;
+            let (writer, guard) = tracing_appender::non_blocking(file);
+            let layer = fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(writer)
+                .with_filter(make_filter()?);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry().with(console).with(json).init();
+    Ok(guard)
+}
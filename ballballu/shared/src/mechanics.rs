@@ -1,4 +1,4 @@
-use crate::objects::{PlayerSpec, Dot};
+use crate::objects::{PlayerSpec, Dot, Cell, Virus};
 
 /// Distance between two points
 fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
@@ -6,19 +6,19 @@ fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
 }
 
 
-/// CellsCollisionsCheck: player-player collision
-/// Returns true if two players' circles overlap
-pub fn cells_collisions_check(a: &PlayerSpec, b: &PlayerSpec) -> bool {
+/// CellsCollisionsCheck: cell-cell collision
+/// Returns true if two cells' circles overlap
+pub fn cells_collisions_check(a: &Cell, b: &Cell) -> bool {
     let d = distance(a.x, a.y, b.x, b.y);
     d < (a.radius + b.radius)
 }
 
 
-/// DotCollisionCheck: player eats dot
-/// Returns true if player overlaps with the dot
-pub fn dot_collision_check(player: &PlayerSpec, dot: &Dot) -> bool {
-    let d = distance(player.x, player.y, dot.x, dot.y);
-    d < (player.radius + dot.radius)
+/// DotCollisionCheck: cell eats dot
+/// Returns true if the cell overlaps with the dot
+pub fn dot_collision_check(cell: &Cell, dot: &Dot) -> bool {
+    let d = distance(cell.x, cell.y, dot.x, dot.y);
+    d < (cell.radius + dot.radius)
 }
 
 
@@ -42,27 +42,29 @@ pub fn calculate_radius_from_score(score: u32, base_radius: f32) -> f32 {
 /// UpdatePosition: update player movement
 /// Moves a player with discrete distance-based movement.
 /// Consumes remaining_distance each frame and stops when complete.
+/// All cells move together; split cells additionally drift with their
+/// decaying launch boost and are slowly pulled back towards the player's
+/// center. Each cell is clamped to stay inside the world.
 pub fn update_position(
     player: &mut PlayerSpec,
     speed: f32,
-    delta_time_ms: f32
+    delta_time_ms: f32,
+    world_width: f32,
+    world_height: f32,
 ) {
-    const WORLD_WIDTH: f32 = 2000.0;
-    const WORLD_HEIGHT: f32 = 2000.0;
+    let dt_sec = delta_time_ms / 1000.0;
+    let mut dx = 0.0;
+    let mut dy = 0.0;
 
     // Only move if there's remaining distance
     if player.remaining_distance > 0.0 {
-        let dt_sec = delta_time_ms / 1000.0;
         let distance_this_frame = (speed * dt_sec).min(player.remaining_distance);
         
         // Calculate direction magnitude
         let vel_mag = (player.vx * player.vx + player.vy * player.vy).sqrt();
         if vel_mag > 0.0 {
-            let dx = (player.vx / vel_mag) * distance_this_frame;
-            let dy = (player.vy / vel_mag) * distance_this_frame;
-            
-            player.x += dx;
-            player.y += dy;
+            dx = (player.vx / vel_mag) * distance_this_frame;
+            dy = (player.vy / vel_mag) * distance_this_frame;
             player.remaining_distance -= distance_this_frame;
         }
         
@@ -73,22 +75,490 @@ pub fn update_position(
             player.vy = 0.0;
         }
     }
-    
-    // Clamp position to world boundaries to prevent going off-screen
-    player.x = player.x.clamp(player.radius, WORLD_WIDTH - player.radius);
-    player.y = player.y.clamp(player.radius, WORLD_HEIGHT - player.radius);
+
+    // Boost decays exponentially (about 95% gone after 0.75s)
+    let boost_decay = (-SPLIT_BOOST_DECAY_PER_SEC * dt_sec).exp();
+    let (center_x, center_y) = (player.x, player.y);
+    for cell in player.cells.iter_mut() {
+        // Pull towards the center so split cells come back together
+        let to_center = distance(cell.x, cell.y, center_x, center_y);
+        if to_center > 0.0 {
+            let pull = (CELL_PULL_SPEED * dt_sec).min(to_center);
+            cell.x += (center_x - cell.x) / to_center * pull;
+            cell.y += (center_y - cell.y) / to_center * pull;
+        }
+
+        cell.x += dx + cell.boost_vx * dt_sec;
+        cell.y += dy + cell.boost_vy * dt_sec;
+        cell.boost_vx *= boost_decay;
+        cell.boost_vy *= boost_decay;
+        if cell.boost_vx.abs() < 1.0 && cell.boost_vy.abs() < 1.0 {
+            cell.boost_vx = 0.0;
+            cell.boost_vy = 0.0;
+        }
+
+        // Clamp position to world boundaries to prevent going off-screen
+        cell.x = clamp_to_world(cell.x, cell.radius, world_width);
+        cell.y = clamp_to_world(cell.y, cell.radius, world_height);
+    }
+
+    refresh_center(player);
+}
+
+/// StartStep: begin a discrete move of `distance` in direction (dx, dy)
+/// Ignored while the previous step is still running. Returns true if the
+/// step started.
+pub fn start_step(player: &mut PlayerSpec, dx: f32, dy: f32, distance: f32) -> bool {
+    let mag = (dx * dx + dy * dy).sqrt();
+    if player.remaining_distance > 0.0 || distance <= 0.0 || mag <= 0.0 {
+        return false;
+    }
+    player.vx = dx / mag * player.speed;
+    player.vy = dy / mag * player.speed;
+    player.remaining_distance = distance;
+    true
+}
+
+const SPLIT_BOOST_DECAY_PER_SEC: f32 = 4.0;
+const CELL_PULL_SPEED: f32 = 60.0; // units/sec
+
+
+/// SteerPlayer: continuous movement towards a target offset
+/// (dx, dy) is the target relative to the player's center. Full speed when
+/// the target is at least one radius away, slower when closer.
+/// Queues two ticks of distance so the velocity stays set (for client
+/// prediction) until the next input arrives; `update_position` does the move.
+pub fn steer_player(player: &mut PlayerSpec, dx: f32, dy: f32, delta_time_ms: f32) {
+    let mag = (dx * dx + dy * dy).sqrt();
+    if mag <= 0.0 {
+        return;
+    }
+    let throttle = (mag / player.radius.max(1.0)).min(1.0);
+    let speed = player.speed * throttle;
+    player.vx = dx / mag * speed;
+    player.vy = dy / mag * speed;
+    player.remaining_distance = 2.0 * speed * delta_time_ms / 1000.0;
 }
 
 
 /// ConsumeCalculation: size updates after consuming
-/// When big_player eats small_player:
+/// When big_cell eats small_cell:
 /// - new score = sum
 /// - new radius = recalculated from score
 pub fn consume_calculation(
-    big_player: &mut PlayerSpec,
-    small_player: &PlayerSpec,
+    big_cell: &mut Cell,
+    small_cell: &Cell,
     base_radius: f32
 ) {
-    big_player.score += small_player.score;
-    big_player.radius = calculate_radius_from_score(big_player.score, base_radius);
+    big_cell.score += small_cell.score;
+    big_cell.radius = calculate_radius_from_score(big_cell.score, base_radius);
+}
+
+
+/// DecayCell: shed part of the score above `threshold`
+/// `fraction` of the excess is lost (e.g. 0.01 for 1% this tick). Scores are
+/// whole numbers, so the fractional part is kept in `carry` for next time.
+/// Returns the score removed.
+pub fn decay_cell(cell: &mut Cell, threshold: u32, fraction: f32, carry: &mut f32, base_radius: f32) -> u32 {
+    if cell.score <= threshold {
+        *carry = 0.0;
+        return 0;
+    }
+    *carry += (cell.score - threshold) as f32 * fraction;
+    let lost = (carry.floor() as u32).min(cell.score - threshold);
+    *carry -= lost as f32;
+    if lost > 0 {
+        cell.score -= lost;
+        cell.radius = calculate_radius_from_score(cell.score, base_radius);
+    }
+    lost
+}
+
+
+/// RefreshCenter: player position = score-weighted center of its cells
+/// (every cell weighs at least 1 so fresh cells still count)
+pub fn refresh_center(player: &mut PlayerSpec) {
+    let total_weight: f32 = player.cells.iter().map(|c| c.score.max(1) as f32).sum();
+    if total_weight > 0.0 {
+        player.x = player.cells.iter().map(|c| c.x * c.score.max(1) as f32).sum::<f32>() / total_weight;
+        player.y = player.cells.iter().map(|c| c.y * c.score.max(1) as f32).sum::<f32>() / total_weight;
+    }
+}
+
+
+/// RefreshPlayer: recompute totals after any cell changed
+/// - score = sum of cell scores
+/// - radius = radius of one cell holding the whole score
+/// - x / y = center of the cells
+pub fn refresh_player(player: &mut PlayerSpec, base_radius: f32) {
+    player.score = player.cells.iter().map(|c| c.score).sum();
+    player.radius = calculate_radius_from_score(player.score, base_radius);
+    refresh_center(player);
+}
+
+
+/// Rules for splitting cells (server configuration)
+#[derive(Debug, Clone, Copy)]
+pub struct SplitRules {
+    pub min_score: u32,        // cells below this score do not split
+    pub max_cells: usize,      // per player
+    pub launch_speed: f32,     // initial speed of the new half (units/sec)
+    pub merge_delay_ticks: u64, // split cells stay apart this long
+}
+
+
+/// SplitCells: split every cell with enough score into two halves.
+/// The new half is launched in direction (dir_x, dir_y); both halves may
+/// merge again from `tick + merge_delay_ticks`.
+/// New cell ids come from `next_cell_id`. Returns the number of new cells.
+pub fn split_cells(
+    player: &mut PlayerSpec,
+    dir_x: f32,
+    dir_y: f32,
+    rules: &SplitRules,
+    tick: u64,
+    base_radius: f32,
+    next_cell_id: &mut u64,
+) -> usize {
+    let mag = (dir_x * dir_x + dir_y * dir_y).sqrt();
+    let (nx, ny) = if mag > 0.0 { (dir_x / mag, dir_y / mag) } else { (1.0, 0.0) };
+    let merge_tick = tick + rules.merge_delay_ticks;
+
+    let mut new_cells = Vec::new();
+    let existing = player.cells.len();
+    for cell in player.cells.iter_mut() {
+        if existing + new_cells.len() >= rules.max_cells {
+            break;
+        }
+        if cell.score < rules.min_score {
+            continue;
+        }
+
+        let half = cell.score / 2;
+        cell.score -= half;
+        cell.radius = calculate_radius_from_score(cell.score, base_radius);
+        cell.merge_tick = merge_tick;
+
+        let radius = calculate_radius_from_score(half, base_radius);
+        let id = *next_cell_id;
+        *next_cell_id += 1;
+        new_cells.push(Cell {
+            id,
+            // Start just touching the parent, in front of it
+            x: cell.x + nx * (cell.radius + radius),
+            y: cell.y + ny * (cell.radius + radius),
+            radius,
+            score: half,
+            boost_vx: nx * rules.launch_speed,
+            boost_vy: ny * rules.launch_speed,
+            merge_tick,
+        });
+    }
+
+    let count = new_cells.len();
+    player.cells.extend(new_cells);
+    refresh_player(player, base_radius);
+    count
+}
+
+
+/// MergeOrSeparateCells: resolve overlap between cells of one player
+/// - both cells past their merge_tick and one center inside the other => merge
+/// - otherwise overlapping cells are pushed apart
+pub fn merge_or_separate_cells(player: &mut PlayerSpec, tick: u64, base_radius: f32) {
+    let mut i = 0;
+    while i < player.cells.len() {
+        let mut j = i + 1;
+        while j < player.cells.len() {
+            let (a, b) = (&player.cells[i], &player.cells[j]);
+            let d = distance(a.x, a.y, b.x, b.y);
+            let can_merge = a.merge_tick <= tick && b.merge_tick <= tick;
+
+            if can_merge && d < a.radius.max(b.radius) {
+                let eaten = player.cells.remove(j);
+                consume_calculation(&mut player.cells[i], &eaten, base_radius);
+                continue;
+            }
+
+            let overlap = a.radius + b.radius - d;
+            if !can_merge && overlap > 0.0 {
+                // Push both halves apart along the line between centers
+                let (ux, uy) = if d > 0.0 { ((b.x - a.x) / d, (b.y - a.y) / d) } else { (1.0, 0.0) };
+                let push = overlap / 2.0;
+                player.cells[i].x -= ux * push;
+                player.cells[i].y -= uy * push;
+                player.cells[j].x += ux * push;
+                player.cells[j].y += uy * push;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    refresh_player(player, base_radius);
+}
+
+
+/// EjectMass: take `cost` score from a cell for an ejected pellet
+/// Cells below `min_score` cannot eject; returns false and leaves them as is.
+/// The cell shrinks via `calculate_radius_from_score`.
+pub fn eject_from_cell(cell: &mut Cell, cost: u32, min_score: u32, base_radius: f32) -> bool {
+    if cell.score < min_score.max(cost) {
+        return false;
+    }
+    cell.score -= cost;
+    cell.radius = calculate_radius_from_score(cell.score, base_radius);
+    true
+}
+
+
+/// UpdateDotPosition: move an ejected pellet
+/// Velocity decays exponentially until the pellet stops; pellets stay
+/// inside the world.
+pub fn update_dot_position(dot: &mut Dot, delta_time_ms: f32, world_width: f32, world_height: f32) {
+    if dot.vx == 0.0 && dot.vy == 0.0 {
+        return;
+    }
+    let mut pos = (dot.x, dot.y);
+    let mut vel = (dot.vx, dot.vy);
+    slide(&mut pos, &mut vel, dot.radius, delta_time_ms / 1000.0, (world_width, world_height));
+    (dot.x, dot.y) = pos;
+    (dot.vx, dot.vy) = vel;
+}
+
+
+/// UpdateVirusPosition: move a freshly fired virus
+/// Same sliding rule as ejected pellets.
+pub fn update_virus_position(virus: &mut Virus, delta_time_ms: f32, world_width: f32, world_height: f32) {
+    if virus.vx == 0.0 && virus.vy == 0.0 {
+        return;
+    }
+    let mut pos = (virus.x, virus.y);
+    let mut vel = (virus.vx, virus.vy);
+    slide(&mut pos, &mut vel, virus.radius, delta_time_ms / 1000.0, (world_width, world_height));
+    (virus.x, virus.y) = pos;
+    (virus.vx, virus.vy) = vel;
+}
+
+/// Move by velocity, decay the velocity and stay inside the world
+fn slide(pos: &mut (f32, f32), vel: &mut (f32, f32), radius: f32, dt_sec: f32, world: (f32, f32)) {
+    pos.0 = clamp_to_world(pos.0 + vel.0 * dt_sec, radius, world.0);
+    pos.1 = clamp_to_world(pos.1 + vel.1 * dt_sec, radius, world.1);
+
+    let decay = (-PELLET_DECAY_PER_SEC * dt_sec).exp();
+    vel.0 *= decay;
+    vel.1 *= decay;
+    if vel.0.abs() < 1.0 && vel.1.abs() < 1.0 {
+        *vel = (0.0, 0.0);
+    }
+}
+
+const PELLET_DECAY_PER_SEC: f32 = 5.0;
+
+/// Keep a circle of `radius` inside [0, extent] on one axis
+/// A circle wider than the world stays at the world's middle.
+fn clamp_to_world(value: f32, radius: f32, extent: f32) -> f32 {
+    let radius = radius.min(extent / 2.0);
+    value.clamp(radius, extent - radius)
+}
+
+
+/// VirusPopsCell: a cell bursts on a virus when it is bigger than the virus
+/// and covers the virus center
+pub fn virus_pops_cell(cell: &Cell, virus: &Virus, size_fraction: f32) -> bool {
+    cell.radius > virus.radius * size_fraction
+        && distance(cell.x, cell.y, virus.x, virus.y) < cell.radius
+}
+
+
+/// PopCell: burst one cell into up to `pieces` cells
+/// The cell keeps half its score, the other half is shared by the new
+/// pieces which fly out evenly in all directions. Limited by
+/// `rules.max_cells`; pieces may merge again after `rules.merge_delay_ticks`.
+/// Returns the number of new cells.
+pub fn pop_cell(
+    player: &mut PlayerSpec,
+    cell_id: u64,
+    pieces: usize,
+    rules: &SplitRules,
+    tick: u64,
+    base_radius: f32,
+    next_cell_id: &mut u64,
+) -> usize {
+    let room = rules.max_cells.saturating_sub(player.cells.len());
+    let Some(cell) = player.cells.iter_mut().find(|c| c.id == cell_id) else {
+        return 0;
+    };
+    let extra = (pieces.saturating_sub(1)).min(room).min((cell.score / 2) as usize);
+    if extra == 0 {
+        return 0;
+    }
+
+    let piece_score = cell.score / 2 / extra as u32;
+    cell.score -= piece_score * extra as u32;
+    cell.radius = calculate_radius_from_score(cell.score, base_radius);
+    let merge_tick = tick + rules.merge_delay_ticks;
+    cell.merge_tick = merge_tick;
+
+    let radius = calculate_radius_from_score(piece_score, base_radius);
+    let (cx, cy) = (cell.x, cell.y);
+    let mut new_cells = Vec::with_capacity(extra);
+    for i in 0..extra {
+        let angle = i as f32 / extra as f32 * std::f32::consts::TAU;
+        let (nx, ny) = (angle.cos(), angle.sin());
+        let id = *next_cell_id;
+        *next_cell_id += 1;
+        new_cells.push(Cell {
+            id,
+            x: cx + nx * radius,
+            y: cy + ny * radius,
+            radius,
+            score: piece_score,
+            boost_vx: nx * rules.launch_speed,
+            boost_vy: ny * rules.launch_speed,
+            merge_tick,
+        });
+    }
+
+    player.cells.extend(new_cells);
+    refresh_player(player, base_radius);
+    extra
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_RADIUS: f32 = 10.0;
+    const RULES: SplitRules = SplitRules { min_score: 20, max_cells: 4, launch_speed: 300.0, merge_delay_ticks: 20 };
+
+    fn cell(id: u64, x: f32, y: f32, score: u32) -> Cell {
+        Cell {
+            id,
+            x,
+            y,
+            radius: calculate_radius_from_score(score, BASE_RADIUS),
+            score,
+            boost_vx: 0.0,
+            boost_vy: 0.0,
+            merge_tick: 0,
+        }
+    }
+
+    fn player(cells: Vec<Cell>) -> PlayerSpec {
+        let mut player = PlayerSpec {
+            id: 1,
+            name: "p".to_string(),
+            x: 0.0,
+            y: 0.0,
+            radius: 0.0,
+            score: 0,
+            speed: 100.0,
+            sequence_number: 0,
+            remaining_distance: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            cells,
+            team: None,
+            bot: false,
+        };
+        refresh_player(&mut player, BASE_RADIUS);
+        player
+    }
+
+    #[test]
+    fn split_halves_cells_and_keeps_the_score() {
+        let mut p = player(vec![cell(1, 500.0, 500.0, 101), cell(2, 800.0, 500.0, 10)]);
+        let mut next_id = 10;
+
+        assert_eq!(split_cells(&mut p, 0.0, 2.0, &RULES, 100, BASE_RADIUS, &mut next_id), 1);
+        assert_eq!(next_id, 11);
+        assert_eq!(p.cells.len(), 3);
+        assert_eq!(p.score, 111);
+        assert_eq!(p.cells[0].score, 51);
+        assert_eq!(p.cells[1].score, 10); // below min_score
+        let new = &p.cells[2];
+        assert_eq!((new.id, new.score), (10, 50));
+        assert!(new.y > p.cells[0].y && new.boost_vy > 0.0 && new.boost_vx == 0.0);
+        assert_eq!(new.merge_tick, 120);
+        assert_eq!(p.cells[0].merge_tick, 120);
+    }
+
+    #[test]
+    fn split_stops_at_max_cells() {
+        let mut p = player(vec![cell(1, 500.0, 500.0, 400)]);
+        let mut next_id = 10;
+
+        assert_eq!(split_cells(&mut p, 1.0, 0.0, &RULES, 0, BASE_RADIUS, &mut next_id), 1);
+        assert_eq!(split_cells(&mut p, 1.0, 0.0, &RULES, 0, BASE_RADIUS, &mut next_id), 2);
+        assert_eq!(split_cells(&mut p, 1.0, 0.0, &RULES, 0, BASE_RADIUS, &mut next_id), 0);
+        assert_eq!(p.cells.len(), RULES.max_cells);
+        assert_eq!(p.score, 400);
+    }
+
+    #[test]
+    fn pop_shares_half_the_score_between_pieces() {
+        let mut p = player(vec![cell(1, 500.0, 500.0, 100)]);
+        let mut next_id = 10;
+
+        assert_eq!(pop_cell(&mut p, 1, 8, &RULES, 0, BASE_RADIUS, &mut next_id), 3); // max_cells
+        assert_eq!(p.cells.len(), 4);
+        assert_eq!(p.cells[0].score, 52);
+        assert!(p.cells[1..].iter().all(|c| c.score == 16 && c.merge_tick == RULES.merge_delay_ticks));
+        assert_eq!(p.score, 100);
+        assert_eq!(pop_cell(&mut p, 1, 8, &RULES, 0, BASE_RADIUS, &mut next_id), 0);
+        assert_eq!(pop_cell(&mut p, 99, 8, &RULES, 0, BASE_RADIUS, &mut next_id), 0);
+    }
+
+    #[test]
+    fn decay_carries_fractions_and_stops_at_the_threshold() {
+        let mut c = cell(1, 0.0, 0.0, 1100);
+        let mut carry = 0.0;
+
+        // 0.4 per call: nothing lost until the carry reaches a whole point
+        assert_eq!(decay_cell(&mut c, 1000, 0.004, &mut carry, BASE_RADIUS), 0);
+        assert_eq!(decay_cell(&mut c, 1000, 0.004, &mut carry, BASE_RADIUS), 0);
+        assert_eq!(decay_cell(&mut c, 1000, 0.004, &mut carry, BASE_RADIUS), 1);
+        assert_eq!(c.score, 1099);
+        assert_eq!(c.radius, calculate_radius_from_score(1099, BASE_RADIUS));
+
+        // Never below the threshold
+        assert_eq!(decay_cell(&mut c, 1000, 2.0, &mut carry, BASE_RADIUS), 99);
+        assert_eq!(c.score, 1000);
+        assert_eq!(decay_cell(&mut c, 1000, 0.5, &mut carry, BASE_RADIUS), 0);
+        assert_eq!(carry, 0.0);
+    }
+
+    #[test]
+    fn split_cells_stay_apart_then_merge_after_the_cooldown() {
+        let mut p = player(vec![cell(1, 500.0, 500.0, 200)]);
+        let mut next_id = 10;
+        split_cells(&mut p, 1.0, 0.0, &RULES, 0, BASE_RADIUS, &mut next_id);
+
+        let mut tick = 0;
+        while tick < RULES.merge_delay_ticks {
+            let speed = p.speed;
+            update_position(&mut p, speed, 50.0, 1000.0, 1000.0);
+            merge_or_separate_cells(&mut p, tick, BASE_RADIUS);
+            assert_eq!(p.cells.len(), 2, "merged at tick {}", tick);
+            assert!(!cells_collisions_check(&p.cells[0], &p.cells[1]));
+            tick += 1;
+        }
+        while p.cells.len() > 1 && tick < RULES.merge_delay_ticks + 200 {
+            let speed = p.speed;
+            update_position(&mut p, speed, 50.0, 1000.0, 1000.0);
+            merge_or_separate_cells(&mut p, tick, BASE_RADIUS);
+            tick += 1;
+        }
+        assert_eq!(p.cells.len(), 1);
+        assert_eq!(p.score, 200);
+        assert_eq!(p.cells[0].radius, calculate_radius_from_score(200, BASE_RADIUS));
+    }
+
+    #[test]
+    fn clamp_keeps_oversized_cells_in_the_middle() {
+        assert_eq!(clamp_to_world(-5.0, 10.0, 100.0), 10.0);
+        assert_eq!(clamp_to_world(95.0, 10.0, 100.0), 90.0);
+        assert_eq!(clamp_to_world(5.0, 80.0, 100.0), 50.0);
+    }
 }
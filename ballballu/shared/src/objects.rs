@@ -1,7 +1,23 @@
 use serde::{Serialize, Deserialize};
 
+/// One circle owned by a player
+/// A player starts with one cell and gets more by splitting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    pub id: u64,            // unique across all players
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub score: u32,
+    pub boost_vx: f32,      // launch velocity after a split, decays to 0
+    pub boost_vy: f32,
+    pub merge_tick: u64,    // may merge with sibling cells from this tick on
+}
+
 /// Player state sent between server and clients
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// x / y / radius / score describe the player as a whole (center of mass,
+/// total score); collisions and rendering use `cells`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PlayerSpec {
     pub id: u64,
     pub name: String,
@@ -10,17 +26,23 @@ pub struct PlayerSpec {
     pub radius: f32,
     pub score: u32,
     pub speed: f32,          // movement speed
-    pub sequence_number: u64, // counter for inputs / ordering
+    pub sequence_number: u64, // last `Input` / `Move` the server took (client reconciliation)
     #[serde(default)]
     pub remaining_distance: f32, // distance left to move in current direction
     #[serde(default)]
     pub vx: f32,             // velocity x component
     #[serde(default)]
     pub vy: f32,             // velocity y component
+    pub cells: Vec<Cell>,    // never empty while the player is alive
+    #[serde(default)]
+    pub team: Option<u8>,    // team mode only; teammates cannot eat each other
+    #[serde(default)]
+    pub bot: bool,           // server-side AI player
 }
 
 /// Food dots on the map
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Also used for mass ejected by players (see `ClientMessage::EjectMass`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Dot {
     pub id: u64,
     pub x: f32,
@@ -28,4 +50,66 @@ pub struct Dot {
     pub radius: f32,
     pub color: (u8, u8, u8),
     pub score: u32, // Score value of this dot (2, 5, or 10)
+    #[serde(default)]
+    pub vx: f32,    // only ejected pellets move, slowing down to a stop
+    #[serde(default)]
+    pub vy: f32,
+}
+
+/// Green spiky obstacle
+/// Smaller cells can hide behind it; a cell big enough to cover it bursts
+/// into pieces. Feeding it ejected mass makes it fire a new virus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Virus {
+    pub id: u64,
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub fed: u32,   // pellets absorbed since it last fired
+    pub vx: f32,    // only freshly fired viruses move, slowing down to a stop
+    pub vy: f32,
+}
+
+/// One player in the lobby roster
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LobbyEntry {
+    pub id: u64,
+    pub name: String,
+    pub ready: bool,
+    pub team: Option<u8>,
+}
+
+/// Final standing of one player when a round ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub rank: usize,       // 1 = winner
+    pub id: u64,
+    pub name: String,
+    pub score: u32,        // score when the round ended, decides the rank
+    pub peak_score: u32,   // highest score during the round
+    pub dots_eaten: u32,   // food dots and pellets
+    pub cells_eaten: u32,  // cells of other players
+    pub times_eaten: u32,  // lost the last cell and respawned
+    pub team: Option<u8>,
+    pub bot: bool,
+}
+
+/// One row of the global leaderboard summary
+/// Sent with every snapshot so clients see the top players even when
+/// those players are outside their area of interest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub id: u64,
+    pub name: String,
+    pub score: u32,
+    pub team: Option<u8>,
+    pub bot: bool,
+}
+
+/// Combined score of one team (team mode only)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamScore {
+    pub team: u8,
+    pub score: u32,     // sum of the members' scores
+    pub players: usize,
 }
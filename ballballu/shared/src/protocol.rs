@@ -1,14 +1,17 @@
 use serde::{Serialize, Deserialize};
 use crate::{GameConstant, GameSnapshot};
+use crate::delta::SnapshotDelta;
 
 
 /// Client → Server Messages
-/// Client input command (WASD)
+/// Steering input for `MovementMode::Continuous` (mouse cursor)
+/// (dx, dy) is the cursor relative to the player's center in world units:
+/// the direction sets the heading, the length (up to one radius) the speed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInput {
-    pub dx: f32,              // movement direction x
-    pub dy: f32,              // movement direction y
-    pub sequence_number: u64, // ensures ordering
+    pub dx: f32,              // target offset x
+    pub dy: f32,              // target offset y
+    pub sequence_number: u64, // increasing per client, older inputs are dropped
 }
 
 /// Messages sent from the client to the server
@@ -17,15 +20,49 @@ pub enum ClientMessage {
     /// New client wants to join with a username
     Join { name: String },
 
-    /// Movement input (continuous, deprecated in favor of Move)
+    /// Continuous steering input (only used in `MovementMode::Continuous`)
     Input { input: UserInput },
 
     /// Discrete movement: move a fixed distance in direction
-    Move { dx: f32, dy: f32, distance: f32 },
+    /// Shares the sequence numbers of `Input` (client reconciliation).
+    Move { dx: f32, dy: f32, distance: f32, sequence_number: u64 },
+
+    /// Split every cell that is big enough, new halves fly in the last move direction
+    Split,
+
+    /// Shoot a small pellet of mass from every cell that is big enough
+    EjectMass,
 
     /// Player is ready to start the game (pressed space)
     Ready,
 
+    /// Player takes back its Ready while the lobby is still waiting
+    Unready,
+
+    /// Switch team in team mode (not while a round is running)
+    ChooseTeam { team: u8 },
+
+    /// Client has the snapshot for this tick; later updates may be deltas against it
+    /// `epoch` is the one of the newest Welcome; acks from an older epoch
+    /// (another world) are ignored.
+    Ack { tick: u64, epoch: u64 },
+
+    /// Reconnected client takes back its old player (token from `WelcomeMessage`)
+    /// The server answers with a new Welcome for the old player id; an
+    /// unknown or expired token leaves the client on its fresh player.
+    Resume { token: String },
+
+    /// Ask for the list of rooms (answered with `ServerMessage::RoomList`)
+    ListRooms,
+
+    /// Move to the named room, which is created if it does not exist yet
+    /// The player starts over there; a new Welcome carries that room's
+    /// constants. A full room is refused with `RoomListMessage::error`.
+    JoinRoom { room: String },
+
+    /// Ask for the best lives ever recorded (answered with `ServerMessage::HallOfFame`)
+    HallOfFame,
+
     /// Client gracefully disconnects
     Quit,
 }
@@ -37,6 +74,45 @@ pub enum ClientMessage {
 pub struct WelcomeMessage {
     pub player_id: u64,           // assigned by server
     pub constants: GameConstant,  // game constants
+    pub resume_token: String,     // send back in `ClientMessage::Resume` after a reconnect
+    pub room: String,             // name of the room the player is in
+    pub epoch: u64,               // snapshot numbering, changes when the connection moves to another world
+}
+
+/// One entry of the room list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomInfo {
+    pub id: u64,
+    pub name: String,
+    pub players: usize,
+    pub max_players: usize,
+}
+
+/// Rooms on this server, sent on `ClientMessage::ListRooms` / `JoinRoom`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomListMessage {
+    pub rooms: Vec<RoomInfo>,
+    pub current: String,         // room of the receiving player
+    pub error: Option<String>,   // why a JoinRoom was refused
+}
+
+/// One finished life in the hall of fame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFameEntry {
+    pub name: String,
+    pub peak_score: u32,
+    pub players_eaten: u32,
+    pub dots_eaten: u32,
+    pub survival_ms: u64, // time alive while the round was running
+    pub ended_at: u64,    // unix time (seconds)
+}
+
+/// Best single lives by peak score, sent on `ClientMessage::HallOfFame`
+/// Both lists are empty if the server keeps no hall of fame.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HallOfFameMessage {
+    pub all_time: Vec<HallOfFameEntry>,
+    pub daily: Vec<HallOfFameEntry>, // lives that ended today (UTC)
 }
 
 /// Normal broadcast update from server every tick
@@ -45,17 +121,34 @@ pub struct StateUpdateMessage {
     pub snapshot: GameSnapshot,   // full world snapshot
 }
 
+/// Changes since a snapshot the client acknowledged (see `ClientMessage::Ack`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaUpdateMessage {
+    pub delta: SnapshotDelta,
+}
+
 /// Server instructs client to exit or rejoin
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ByeMessage {
     pub reason: String,
 }
 
+/// Message from the server admin, shown to every player as a banner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnouncementMessage {
+    pub text: String,
+    pub duration_ms: u64, // how long the banner stays on screen
+}
+
 
 /// Enum of all possible server → client packets
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerMessage {
     Welcome(WelcomeMessage),
-    StateUpdate(StateUpdateMessage),
+    StateUpdate(StateUpdateMessage),  // full snapshot (keyframe)
+    DeltaUpdate(DeltaUpdateMessage),
+    RoomList(RoomListMessage),
+    HallOfFame(HallOfFameMessage),
     Bye(ByeMessage),
+    Announcement(AnnouncementMessage),
 }
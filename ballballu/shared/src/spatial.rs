@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+
+/// Spatial Grid
+/// Uniform-grid spatial hash for circles.
+/// Every entity is stored in each cell its bounding box touches, so a query
+/// only has to look at the cells around the query circle instead of every
+/// entity in the world.
+///    - cell_size should be around the diameter of typical entities
+///    - ids are whatever the caller uses (player id, dot id)
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<u64>>,
+    entries: HashMap<u64, GridEntry>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GridEntry {
+    x: f32,
+    y: f32,
+    radius: f32,
+    min_cell: (i32, i32),
+    max_cell: (i32, i32),
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell_size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    fn cell_range(&self, x: f32, y: f32, radius: f32) -> ((i32, i32), (i32, i32)) {
+        (self.cell_of(x - radius, y - radius), self.cell_of(x + radius, y + radius))
+    }
+
+    /// Insert (or move) a circle
+    pub fn insert(&mut self, id: u64, x: f32, y: f32, radius: f32) {
+        let (min_cell, max_cell) = self.cell_range(x, y, radius);
+
+        if let Some(old) = self.entries.get_mut(&id) {
+            // Same cells: only the stored position changes
+            if old.min_cell == min_cell && old.max_cell == max_cell {
+                old.x = x;
+                old.y = y;
+                old.radius = radius;
+                return;
+            }
+            self.remove(id);
+        }
+
+        for cx in min_cell.0..=max_cell.0 {
+            for cy in min_cell.1..=max_cell.1 {
+                self.cells.entry((cx, cy)).or_default().push(id);
+            }
+        }
+        self.entries.insert(id, GridEntry { x, y, radius, min_cell, max_cell });
+    }
+
+    /// Remove a circle, returns false if it was not in the grid
+    pub fn remove(&mut self, id: u64) -> bool {
+        let Some(entry) = self.entries.remove(&id) else {
+            return false;
+        };
+        for cx in entry.min_cell.0..=entry.max_cell.0 {
+            for cy in entry.min_cell.1..=entry.max_cell.1 {
+                if let Some(ids) = self.cells.get_mut(&(cx, cy)) {
+                    ids.retain(|other| *other != id);
+                    if ids.is_empty() {
+                        self.cells.remove(&(cx, cy));
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Ids of all circles overlapping the query circle (strict overlap, same
+    /// rule as `mechanics::cells_collisions_check`). Each id appears once.
+    pub fn query(&self, x: f32, y: f32, radius: f32) -> Vec<u64> {
+        let (min_cell, max_cell) = self.cell_range(x, y, radius);
+        let mut found = Vec::new();
+
+        for cx in min_cell.0..=max_cell.0 {
+            for cy in min_cell.1..=max_cell.1 {
+                let Some(ids) = self.cells.get(&(cx, cy)) else {
+                    continue;
+                };
+                for id in ids {
+                    let entry = &self.entries[id];
+                    // Entity spans several cells: only report it from the first
+                    // cell shared with the query range
+                    let first_cx = entry.min_cell.0.max(min_cell.0);
+                    let first_cy = entry.min_cell.1.max(min_cell.1);
+                    if (cx, cy) != (first_cx, first_cy) {
+                        continue;
+                    }
+                    let reach = radius + entry.radius;
+                    if (entry.x - x).powi(2) + (entry.y - y).powi(2) < reach * reach {
+                        found.push(*id);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// True if any stored circle overlaps the query circle
+    pub fn any_overlap(&self, x: f32, y: f32, radius: f32) -> bool {
+        !self.query(x, y, radius).is_empty()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut ids: Vec<u64>) -> Vec<u64> {
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn query_reports_a_circle_spanning_many_cells_once() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, 50.0, 50.0, 35.0); // covers 8x8 cells
+        grid.insert(2, 5.0, 5.0, 2.0);
+
+        assert_eq!(grid.query(50.0, 50.0, 40.0), vec![1]);
+        assert_eq!(sorted(grid.query(10.0, 10.0, 100.0)), vec![1, 2]);
+    }
+
+    #[test]
+    fn query_uses_strict_overlap() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, 0.0, 0.0, 5.0);
+
+        assert_eq!(grid.query(9.0, 0.0, 5.0), vec![1]);
+        assert!(grid.query(10.0, 0.0, 5.0).is_empty()); // touching only
+        assert!(!grid.any_overlap(20.0, 20.0, 5.0));
+    }
+
+    #[test]
+    fn insert_again_moves_the_circle() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, 5.0, 5.0, 2.0);
+
+        // Within the same cell
+        grid.insert(1, 7.0, 7.0, 2.0);
+        assert!(grid.query(3.0, 3.0, 1.0).is_empty());
+        assert_eq!(grid.query(7.0, 7.0, 1.0), vec![1]);
+
+        // Far away, and grown over several cells
+        grid.insert(1, 200.0, 200.0, 25.0);
+        assert_eq!(grid.len(), 1);
+        assert!(grid.query(7.0, 7.0, 5.0).is_empty());
+        assert_eq!(grid.query(220.0, 200.0, 1.0), vec![1]);
+        assert_eq!(grid.query(200.0, 200.0, 50.0), vec![1]);
+    }
+
+    #[test]
+    fn remove_clears_every_cell() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, 50.0, 50.0, 30.0);
+        grid.insert(2, 55.0, 55.0, 1.0);
+
+        assert!(grid.remove(1));
+        assert!(!grid.remove(1));
+        assert_eq!(grid.query(50.0, 50.0, 40.0), vec![2]);
+        assert!(grid.remove(2));
+        assert!(grid.is_empty());
+        assert!(grid.cells.is_empty());
+    }
+
+    #[test]
+    fn negative_coordinates_map_to_their_own_cells() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(1, -5.0, -5.0, 2.0);
+        grid.insert(2, 5.0, 5.0, 2.0);
+
+        assert_eq!(grid.query(-5.0, -5.0, 1.0), vec![1]);
+        assert_eq!(sorted(grid.query(0.0, 0.0, 10.0)), vec![1, 2]);
+    }
+}